@@ -0,0 +1,147 @@
+// Throughput of the board rules on 9x9 and 19x19, run with `cargo bench`.
+// Lingo:
+//     playout - a game of uniformly random legal moves that don't fill own eyes, from the empty board to two passes
+//     record  - the moves of one seeded playout, replayed to measure play, scoring, undo and the ko check alone
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use go_board::board::{Board, Color, Loc, Move, MoveKind};
+use go_board::seed::{new_rng, GameRng};
+use rand::seq::SliceRandom;
+
+// Sizes without sentinels
+const SIZES: [usize; 2] = [9, 19];
+const KOMI: f32 = 7.5;
+const SEED: u64 = 7;
+
+fn empty_board(size: usize) -> Board {
+    Board::new(size + 2, size + 2, KOMI)
+}
+
+// The move the playout makes, a pass when no point is left
+fn random_move(board: &Board, rng: &mut GameRng) -> Move {
+    let player = board.get_current_player();
+    let (rows, cols) = board.board_size();
+    let mut points: Vec<Loc> = Loc::get_all_on_board(rows, cols)
+        .into_iter()
+        .filter(|loc| loc.is_on_board((rows, cols)) && board.get(*loc) == Color::Empty)
+        .collect();
+    points.shuffle(rng);
+    let found = points.into_iter().find(|loc| {
+        !board.is_own_eye(*loc, player) && board.move_is_valid(&Move::new(player, *loc))
+    });
+    match found {
+        Some(loc) => Move::new(player, loc),
+        None => Move {
+            player,
+            kind: MoveKind::Pass,
+        },
+    }
+}
+
+// Plays until two passes or a move limit, long playouts only come from repeated captures
+fn playout(size: usize, rng: &mut GameRng) -> Board {
+    let mut board = empty_board(size);
+    let max_moves = 3 * size * size;
+    while !board.is_over() && board.get_game_history().len() < max_moves {
+        let mv = random_move(&board, rng);
+        board.play(&mv);
+    }
+    board
+}
+
+fn record(size: usize) -> Vec<Move> {
+    playout(size, &mut new_rng(SEED)).get_game_history().clone()
+}
+
+fn replay(size: usize, moves: &[Move]) -> Board {
+    let mut board = empty_board(size);
+    for mv in moves {
+        board.play(mv);
+    }
+    board
+}
+
+fn random_playouts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_playout");
+    group.sample_size(10);
+    for size in SIZES {
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, size| {
+            let mut rng = new_rng(SEED);
+            b.iter(|| playout(*size, &mut rng));
+        });
+    }
+    group.finish();
+}
+
+// Every move of the record goes through move_is_valid and removes the stones it captures
+fn play_with_captures(c: &mut Criterion) {
+    let mut group = c.benchmark_group("play");
+    group.sample_size(10);
+    for size in SIZES {
+        let moves = record(size);
+        group.throughput(Throughput::Elements(moves.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &moves, |b, moves| {
+            b.iter(|| replay(size, black_box(moves)));
+        });
+    }
+    group.finish();
+}
+
+// count_score asks about dead stones on stdin, area_score counts stones and territory of the position as it is
+fn area_scoring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("area_score");
+    for size in SIZES {
+        let board = replay(size, &record(size));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter(|| black_box(board).area_score());
+        });
+    }
+    group.finish();
+}
+
+fn undo(c: &mut Criterion) {
+    let mut group = c.benchmark_group("undo");
+    for size in SIZES {
+        let board = replay(size, &record(size));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter(|| black_box(board).clone().undo());
+        });
+    }
+    group.finish();
+}
+
+// Legality of every empty point in the middle of the game, each check looks at the ko point and the neighbours only
+fn ko_check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("move_is_valid");
+    for size in SIZES {
+        let moves = record(size);
+        let board = replay(size, &moves[..moves.len() / 2]);
+        let player = board.get_current_player();
+        let (rows, cols) = board.board_size();
+        let points: Vec<Loc> = Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| loc.is_on_board((rows, cols)) && board.get(*loc) == Color::Empty)
+            .collect();
+        group.throughput(Throughput::Elements(points.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &points, |b, points| {
+            b.iter(|| {
+                points
+                    .iter()
+                    .filter(|loc| board.move_is_valid(&Move::new(player, **loc)))
+                    .count()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    random_playouts,
+    play_with_captures,
+    area_scoring,
+    undo,
+    ko_check
+);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+// Any text typed as a coordinate, by a local player or through the server, goes through the rules without panicking.
+
+#![no_main]
+
+use go_board::board::{Board, Loc, Move, Player};
+use go_board::referee::{Probe, ProbeRules, Referee};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let loc = match Loc::from_string(&text) {
+        Some(loc) => loc,
+        None => return,
+    };
+
+    let mut board = Board::new(11, 11, 7.5);
+    board.place_stone(Loc { row: 5, col: 5 }, Player::White);
+    let mv = Move::new(Player::Black, loc);
+    board.play(&mv);
+    board.get(loc);
+    board.count_liberties(loc);
+    board.group_stones(loc);
+    board.region(loc);
+    board.place_stone(loc, Player::White);
+    board.remove_group(loc);
+    board.final_score();
+    let _ = board.undo();
+
+    let mut referee = Referee::new(11, 11, 7.5);
+    referee.set_probe_rules(Some(ProbeRules::default()));
+    referee.probe(Probe::Occupied(loc));
+    referee.attempt(loc);
+    referee.attempt(loc);
+    referee.final_score();
+});
@@ -0,0 +1,72 @@
+// Random streams of attempts and probes, on and off the board, under random probe and attempt rules.
+// Both players follow the game through their knowledge models like agents do.
+
+#![no_main]
+
+use go_board::board::{Loc, Player};
+use go_board::knowledge::KnowledgeModel;
+use go_board::referee::{AttemptRules, Probe, ProbeCost, ProbeRules, Referee};
+use libfuzzer_sys::fuzz_target;
+
+fn cost(byte: u8) -> ProbeCost {
+    match byte % 3 {
+        0 => ProbeCost::Free,
+        1 => ProbeCost::Pass,
+        _ => ProbeCost::Points(byte as f32 / 16.0),
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (header, moves) = match data.split_first_chunk::<4>() {
+        Some(split) => split,
+        None => return,
+    };
+    // Sizes include sentinels, from a 1x1 to a 19x19 board
+    let size = 3 + header[0] as usize % 19;
+    let mut referee = Referee::new(size, size, 0.5);
+    if header[1] % 2 == 1 {
+        referee.set_probe_rules(Some(ProbeRules {
+            occupied: cost(header[1] / 2),
+            any_capture: cost(header[1] / 8),
+        }));
+    }
+    referee.set_attempt_rules(AttemptRules {
+        pass_after: Some(header[2] as usize % 8).filter(|after| *after > 0),
+        forfeit_after: Some(header[3] as usize % 32).filter(|after| *after > 0),
+        reveal_to_opponent: header[2] >= 128,
+        points_per_rejection: (header[3] / 32) as f32,
+    });
+    let mut black = KnowledgeModel::new(Player::Black, size, size);
+    let mut white = KnowledgeModel::new(Player::White, size, size);
+
+    for pair in moves.chunks_exact(2) {
+        // Rows and columns a little past the sentinels, the top values are special
+        let announcements = match (pair[0], pair[1]) {
+            (255, _) => referee.pass(),
+            (254, _) => referee.resign(),
+            (253, _) => referee.probe(Probe::AnyCapture),
+            (252, col) => referee.probe(Probe::Occupied(Loc {
+                row: col as usize % (size + 2),
+                col: col as usize / 16,
+            })),
+            (row, col) => referee.attempt(Loc {
+                row: row as usize % (size + 2),
+                col: col as usize % (size + 2),
+            }),
+        };
+        for announcement in &announcements {
+            black.update(announcement);
+            white.update(announcement);
+        }
+        if referee.is_over() {
+            break;
+        }
+    }
+
+    referee.final_score();
+    for player in [Player::Black, Player::White] {
+        referee.get_view(player).to_string();
+    }
+    black.to_string();
+    white.to_string();
+});
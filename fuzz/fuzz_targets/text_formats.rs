@@ -0,0 +1,54 @@
+// The text formats we read from outside: board diagrams, SGF results, GTP commands and GTP/KGS time settings.
+// There is no full SGF parser yet, new parsers belong in this target as well.
+
+#![no_main]
+
+use go_board::board::{Board, GameResult, Player};
+use go_board::clock::{Clock, MockTime, TimeControl};
+use go_board::gtp::Engine;
+use libfuzzer_sys::fuzz_target;
+use std::time::Duration;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+
+    if let Ok(board) = Board::from_diagram(&text) {
+        // Whatever was read is written back in a form that reads to the same position
+        let diagram = board.to_diagram();
+        let again = Board::from_diagram(&diagram).expect("a written diagram reads back");
+        assert_eq!(again.to_diagram(), diagram);
+        board.final_score();
+    }
+
+    if let Some(result) = GameResult::from_sgf(&text) {
+        result.to_sgf();
+        result.to_string();
+    }
+
+    let args: Vec<&str> = text.split_whitespace().collect();
+    let controls = [TimeControl::from_gtp(&args), TimeControl::from_kgs(&args)];
+    for control in controls.into_iter().flatten() {
+        let time = MockTime::new();
+        let mut clock = Clock::new(control, Box::new(time.clone()));
+        // Turn lengths come from the input too, up to about an hour each
+        for (i, byte) in data.iter().take(16).enumerate() {
+            let player = if i % 2 == 0 {
+                Player::Black
+            } else {
+                Player::White
+            };
+            clock.start(player);
+            time.advance(Duration::from_secs(*byte as u64 * 15));
+            clock.stop();
+            clock.move_budget(player);
+        }
+        clock.to_string();
+    }
+
+    // Every line a command, a small board keeps genmove quick
+    let mut engine = Engine::new(Box::new(MockTime::new()), 1);
+    engine.respond("boardsize 5");
+    for line in text.lines().take(64) {
+        engine.respond(line);
+    }
+});
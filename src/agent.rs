@@ -0,0 +1,80 @@
+use crate::board::MoveKind;
+use crate::referee::{Announcement, PlayerView, Probe};
+use crate::seed::{new_rng, GameRng};
+use rand::Rng;
+use std::time::Duration;
+
+pub trait Agent {
+    fn name(&self) -> String;
+
+    // Asked again after every rejected attempt, the rejection is already visible in the view
+    fn gen_move(&mut self, view: &PlayerView) -> MoveKind;
+
+    // Asked before every attempt when the game allows probes, None goes on to gen_move
+    fn probe(&mut self, _view: &PlayerView) -> Option<Probe> {
+        None
+    }
+
+    // Told at the start of every turn how long the agent should think at most
+    fn set_time_budget(&mut self, _budget: Duration) {}
+
+    // Agents draw all their randomness from the seed, so the same seed gives the same game
+    fn new_game(&mut self, _seed: u64) {}
+
+    // Receives every announcement addressed to the agent's player
+    fn observe(&mut self, _announcement: &Announcement) {}
+}
+
+// Plays uniformly among points that look empty, without filling its own eyes
+pub struct RandomAgent {
+    rng: GameRng,
+    pass_probability: f64,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        RandomAgent::with_pass_probability(0.0, seed)
+    }
+
+    pub fn with_pass_probability(pass_probability: f64, seed: u64) -> Self {
+        RandomAgent {
+            rng: new_rng(seed),
+            pass_probability,
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> String {
+        if self.pass_probability > 0.0 {
+            format!("random-pass{}", self.pass_probability)
+        } else {
+            String::from("random")
+        }
+    }
+
+    fn new_game(&mut self, seed: u64) {
+        self.rng = new_rng(seed);
+    }
+
+    fn gen_move(&mut self, view: &PlayerView) -> MoveKind {
+        let candidates = view.candidate_moves();
+        if candidates.is_empty() || self.rng.gen_bool(self.pass_probability) {
+            return MoveKind::Pass;
+        }
+        MoveKind::Play(candidates[self.rng.gen_range(0..candidates.len())])
+    }
+}
+
+// Always passes, the weakest possible baseline
+pub struct PassAgent;
+
+impl Agent for PassAgent {
+    fn name(&self) -> String {
+        String::from("pass")
+    }
+
+    fn gen_move(&mut self, _view: &PlayerView) -> MoveKind {
+        MoveKind::Pass
+    }
+}
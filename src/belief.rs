@@ -0,0 +1,259 @@
+// Soft beliefs about where the hidden opponent stones are.
+// Lingo:
+//     determinization - a full board sampled to agree with everything the player knows
+//     prior           - how likely a point is to be played before anything is known about it
+
+use crate::board::{Board, Color, Loc, Player};
+use crate::knowledge::{KnowledgeModel, Known};
+use crate::seed::GameRng;
+use rand::Rng;
+use std::fmt;
+
+// Rejected determinizations are resampled this many times before giving up on the sample
+const MAX_TRIES: usize = 20;
+
+// Players rarely start on the first line and seldom on the second
+fn line_prior(loc: Loc, board_size: (usize, usize)) -> f64 {
+    let (rows, cols) = board_size;
+    let line = loc
+        .row
+        .min(loc.col)
+        .min(rows - 1 - loc.row)
+        .min(cols - 1 - loc.col);
+    match line {
+        1 => 0.3,
+        2 => 0.8,
+        _ => 1.0,
+    }
+}
+
+// Every group needs a liberty, otherwise the position could not have come up in the game
+fn is_consistent(board: &Board, points: &[Loc]) -> bool {
+    points
+        .iter()
+        .all(|loc| board.get(*loc) == Color::Empty || board.count_liberties(*loc) > 0)
+}
+
+// Own and confirmed opponent stones stay where they are, the missing opponent stones go to points that may hold them
+pub fn sample_determinization(knowledge: &KnowledgeModel, rng: &mut GameRng) -> Option<Board> {
+    let (rows, cols) = knowledge.board_size();
+    let player = knowledge.get_player();
+    let points = knowledge.all_points();
+    let confirmed: Vec<Loc> = points
+        .iter()
+        .copied()
+        .filter(|loc| knowledge.get(*loc) == Known::Opponent)
+        .collect();
+    let possible: Vec<Loc> = points
+        .iter()
+        .copied()
+        .filter(|loc| knowledge.can_be_opponent(*loc) && knowledge.get(*loc) != Known::Opponent)
+        .collect();
+    let missing = knowledge
+        .opponent_stone_count()
+        .checked_sub(confirmed.len())?;
+    if missing > possible.len() {
+        return None;
+    }
+
+    for _ in 0..MAX_TRIES {
+        let mut board = Board::new(rows, cols, 0.0);
+        for loc in &points {
+            if knowledge.get(*loc) == Known::Own {
+                board.place_stone(*loc, player);
+            }
+        }
+        for loc in &confirmed {
+            board.place_stone(*loc, player.opponent());
+        }
+
+        // Weighted sampling without replacement, the points with the highest u^(1/w) keys win
+        let mut keys: Vec<(f64, Loc)> = possible
+            .iter()
+            .map(|loc| {
+                let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+                (u.powf(1.0 / line_prior(*loc, (rows, cols))), *loc)
+            })
+            .collect();
+        keys.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, loc) in keys.iter().take(missing) {
+            board.place_stone(*loc, player.opponent());
+        }
+
+        if is_consistent(&board, &points) {
+            board.set_current_player(player);
+            return Some(board);
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone)]
+pub struct BeliefMap {
+    player: Player,
+    probabilities: Vec<Vec<f64>>,
+    // Own stones and sentinels are kept as they are
+    fields: Vec<Vec<Color>>,
+}
+
+impl BeliefMap {
+    // Probability of an opponent stone on each point, averaged over sampled determinizations
+    pub fn estimate(knowledge: &KnowledgeModel, samples: usize, rng: &mut GameRng) -> Self {
+        let (rows, cols) = knowledge.board_size();
+        let player = knowledge.get_player();
+        let opponent = player.opponent().to_color();
+        let mut counts = vec![vec![0.0; cols]; rows];
+        let mut accepted = 0;
+
+        for _ in 0..samples {
+            if let Some(board) = sample_determinization(knowledge, rng) {
+                accepted += 1;
+                for loc in knowledge.all_points() {
+                    if board.get(loc) == opponent {
+                        counts[loc.row][loc.col] += 1.0;
+                    }
+                }
+            }
+        }
+
+        let mut probabilities = vec![vec![0.0; cols]; rows];
+        let mut fields = vec![vec![Color::Invalid; cols]; rows];
+        for loc in knowledge.all_points() {
+            probabilities[loc.row][loc.col] = match knowledge.get(loc) {
+                Known::Opponent => 1.0,
+                _ if accepted > 0 => counts[loc.row][loc.col] / accepted as f64,
+                _ => 0.0,
+            };
+            fields[loc.row][loc.col] = match knowledge.get(loc) {
+                Known::Own => player.to_color(),
+                Known::Opponent => opponent,
+                _ => Color::Empty,
+            };
+        }
+
+        BeliefMap {
+            player,
+            probabilities,
+            fields,
+        }
+    }
+
+    pub fn get(&self, loc: Loc) -> f64 {
+        self.probabilities[loc.row][loc.col]
+    }
+
+    pub fn get_player(&self) -> Player {
+        self.player
+    }
+
+    // Heat map with ANSI background colors, from blue for unlikely to red for likely
+    pub fn to_colored_string(&self) -> String {
+        let mut heat_map = String::new();
+        for (row, fields) in self.fields.iter().enumerate() {
+            for (col, field) in fields.iter().enumerate() {
+                let probability = self.probabilities[row][col];
+                if *field == Color::Empty {
+                    let level = (probability * 5.0).round() as u8;
+                    let color = 16 + 36 * level + (5 - level);
+                    heat_map += &format!(
+                        "\x1B[48;5;{}m{} \x1B[0m",
+                        color,
+                        probability_digit(probability)
+                    );
+                } else {
+                    heat_map += &format!("{} ", field);
+                }
+            }
+            heat_map += "\n";
+        }
+        heat_map
+    }
+}
+
+// . for no chance, 0-9 for tenths, * for certain
+fn probability_digit(probability: f64) -> String {
+    if probability <= 0.0 {
+        String::from(".")
+    } else if probability >= 1.0 {
+        String::from("*")
+    } else {
+        ((probability * 10.0).floor() as u8).to_string()
+    }
+}
+
+impl fmt::Display for BeliefMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row, fields) in self.fields.iter().enumerate() {
+            for (col, field) in fields.iter().enumerate() {
+                if *field == Color::Empty {
+                    write!(f, "{} ", probability_digit(self.probabilities[row][col]))?;
+                } else {
+                    write!(f, "{} ", field)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::belief::{sample_determinization, BeliefMap};
+    use crate::board::{Color, Loc, Player};
+    use crate::referee::Referee;
+    use crate::seed::new_rng;
+
+    #[test]
+    fn probabilities_add_up_to_the_number_of_opponent_stones() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        for loc in [(3, 3), (3, 3), (2, 2), (4, 4)] {
+            referee.attempt(Loc {
+                row: loc.0,
+                col: loc.1,
+            });
+        }
+        let knowledge = referee.get_view(Player::White).get_knowledge();
+
+        let beliefs = BeliefMap::estimate(knowledge, 200, &mut new_rng(1));
+        let total: f64 = knowledge
+            .all_points()
+            .iter()
+            .map(|loc| beliefs.get(*loc))
+            .sum();
+        assert!((total - 2.0).abs() < 1e-9);
+        assert_eq!(beliefs.get(Loc { row: 3, col: 3 }), 1.0);
+        assert_eq!(beliefs.get(Loc { row: 2, col: 2 }), 0.0);
+        assert!(beliefs.get(Loc { row: 3, col: 2 }) > beliefs.get(Loc { row: 1, col: 1 }));
+        assert_eq!(
+            beliefs.to_string().lines().nth(3).unwrap(),
+            "/ 0 1 O 0 0 / "
+        );
+    }
+
+    #[test]
+    fn determinizations_keep_known_stones_and_the_stone_count() {
+        let mut referee = Referee::new(6, 6, 0.5);
+        for loc in [(1, 1), (1, 2), (4, 4), (2, 1)] {
+            referee.attempt(Loc {
+                row: loc.0,
+                col: loc.1,
+            });
+        }
+        let knowledge = referee.get_view(Player::Black).get_knowledge();
+
+        let mut rng = new_rng(5);
+        for _ in 0..20 {
+            let board = sample_determinization(knowledge, &mut rng).unwrap();
+            assert_eq!(board.get(Loc { row: 1, col: 2 }), Color::White);
+            assert_eq!(board.get(Loc { row: 2, col: 1 }), Color::White);
+            assert_eq!(board.get(Loc { row: 4, col: 4 }), Color::Black);
+            let white_stones = knowledge
+                .all_points()
+                .iter()
+                .filter(|loc| board.get(**loc) == Color::White)
+                .count();
+            assert_eq!(white_stones, 2);
+        }
+    }
+}
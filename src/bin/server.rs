@@ -0,0 +1,17 @@
+// Hosts a phantom game for two browsers, each player opens the address and joins a seat.
+// Usage: server [address], e.g. server 0.0.0.0:8080 to play on a LAN
+
+use go_board::server::serve;
+use std::env;
+use std::net::TcpListener;
+
+fn main() {
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("127.0.0.1:8080"));
+    let listener = TcpListener::bind(&address).expect("Failed to bind the address");
+    println!("Open http://{} in two browsers", address);
+    if let Err(error) = serve(listener) {
+        println!("The server stopped: {}", error);
+    }
+}
@@ -1,55 +1,145 @@
 // Lingo:
 //     islands - sets of groups of Color::Empty from the Board
 
+use crate::chain::Chains;
+use crate::symmetry::Symmetry;
 use std::collections::HashSet;
-use std::{io, usize};
+use std::fmt;
+use std::io;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Color {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
     White,
     Black,
     Empty,
     Invalid,
 }
 
-impl Color {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            Color::Empty => ".".into(),
-            Color::White => "#".into(),
-            Color::Black => "O".into(),
-            Color::Invalid => "/".into(),
+            Color::Empty => write!(f, "."),
+            Color::White => write!(f, "#"),
+            Color::Black => write!(f, "O"),
+            Color::Invalid => write!(f, "/"),
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Player {
     White,
     Black,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameResult {
     Player(Player, f32),
     Draw,
+    // The given player won because the opponent's flag fell
+    Time(Player),
+    // The given player won because the opponent resigned
+    Resignation(Player),
+    // The given player won because the opponent broke the rules, e.g. too many illegal attempts
+    Forfeit(Player),
+    // The game doesn't count, e.g. it was abandoned
+    Void,
 }
 
-impl GameResult {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            GameResult::Draw => String::from("D R A W !"),
+            GameResult::Draw => write!(f, "D R A W !"),
             GameResult::Player(player, result) => match player {
-                Player::Black => format!("Black +{}", result),
-                Player::White => format!("White +{}", result),
+                Player::Black => write!(f, "Black +{}", result),
+                Player::White => write!(f, "White +{}", result),
             },
+            GameResult::Time(player) => write!(f, "{:?} wins on time", player),
+            GameResult::Resignation(player) => write!(f, "{:?} wins by resignation", player),
+            GameResult::Forfeit(player) => write!(f, "{:?} wins by forfeit", player),
+            GameResult::Void => write!(f, "No result"),
+        }
+    }
+}
+
+impl GameResult {
+    // Positive when Black is ahead, None when the game wasn't decided by counting
+    pub fn black_margin(&self) -> Option<f32> {
+        match self {
+            GameResult::Player(Player::Black, margin) => Some(*margin),
+            GameResult::Player(Player::White, margin) => Some(-margin),
+            GameResult::Draw => Some(0.0),
+            _ => None,
+        }
+    }
+
+    pub fn get_winner(&self) -> Option<Player> {
+        match self {
+            GameResult::Player(player, _)
+            | GameResult::Time(player)
+            | GameResult::Resignation(player)
+            | GameResult::Forfeit(player) => Some(*player),
+            GameResult::Draw | GameResult::Void => None,
+        }
+    }
+
+    // The value of the SGF RE property, like B+3.5, W+R or 0 for a draw
+    pub fn to_sgf(&self) -> String {
+        let winner = |player: &Player| match player {
+            Player::Black => "B",
+            Player::White => "W",
+        };
+        match self {
+            GameResult::Player(player, margin) => format!("{}+{}", winner(player), margin),
+            GameResult::Time(player) => format!("{}+T", winner(player)),
+            GameResult::Resignation(player) => format!("{}+R", winner(player)),
+            GameResult::Forfeit(player) => format!("{}+F", winner(player)),
+            GameResult::Draw => String::from("0"),
+            GameResult::Void => String::from("Void"),
+        }
+    }
+
+    // Results without a known margin, like B+ or ?, have no GameResult
+    pub fn from_sgf(re: &str) -> Option<Self> {
+        match re {
+            "0" | "Draw" => return Some(GameResult::Draw),
+            "Void" => return Some(GameResult::Void),
+            _ => (),
+        }
+        let (winner, reason) = re.split_once('+')?;
+        let player = match winner {
+            "B" => Player::Black,
+            "W" => Player::White,
+            _ => return None,
+        };
+        match reason {
+            "T" | "Time" => Some(GameResult::Time(player)),
+            "R" | "Resign" => Some(GameResult::Resignation(player)),
+            "F" | "Forfeit" => Some(GameResult::Forfeit(player)),
+            margin => margin
+                .parse()
+                .ok()
+                .map(|margin| GameResult::Player(player, margin)),
+        }
+    }
+
+    pub fn from_black_margin(margin: f32) -> Self {
+        if margin > 0.0 {
+            GameResult::Player(Player::Black, margin)
+        } else if margin < 0.0 {
+            GameResult::Player(Player::White, -margin)
+        } else {
+            GameResult::Draw
         }
     }
 }
 
 // TODO: move closer to the enum
 impl Player {
-    fn to_color(&self) -> Color {
+    pub fn to_color(self) -> Color {
         match self {
             Player::Black => Color::Black,
             Player::White => Color::White,
@@ -65,22 +155,25 @@ impl Player {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Loc {
     pub row: usize,
     pub col: usize,
 }
 
+// Neighbours wrap around instead of overflowing, so stepping off a sentinel or an untrusted
+// location gives a point outside the fields, which Board::get reads as Invalid
 impl Loc {
     fn up(&self) -> Self {
         Loc {
-            row: self.row - 1,
+            row: self.row.wrapping_sub(1),
             col: self.col,
         }
     }
 
     fn down(&self) -> Self {
         Loc {
-            row: self.row + 1,
+            row: self.row.wrapping_add(1),
             col: self.col,
         }
     }
@@ -88,20 +181,22 @@ impl Loc {
     fn left(&self) -> Self {
         Loc {
             row: self.row,
-            col: self.col - 1,
+            col: self.col.wrapping_sub(1),
         }
     }
 
     fn right(&self) -> Self {
         Loc {
             row: self.row,
-            col: self.col + 1,
+            col: self.col.wrapping_add(1),
         }
     }
 
-    // TODO: return all 4 neighbours, use in loops
-    //fn all_4nbr(self) -> Vec<Self> 
-    
+    // For sentinels some of the neighbours lie outside the fields
+    pub fn all_4nbr(self) -> Vec<Self> {
+        vec![self.up(), self.down(), self.left(), self.right()]
+    }
+
     pub fn from_string(s: &str) -> Option<Self> {
         if !s.contains(",") {
             return None;
@@ -120,7 +215,7 @@ impl Loc {
         Some(Loc { row, col })
     }
 
-    fn is_on_board(&self, board_size: (usize, usize)) -> bool {
+    pub fn is_on_board(&self, board_size: (usize, usize)) -> bool {
         let upper_edge_check = self.row > 0;
         let lower_edge_check = self.row < board_size.0 - 1;
         let left_edge_check = self.col > 0;
@@ -130,7 +225,7 @@ impl Loc {
     }
 
     // TODO: introduce struct BoardSize(usize, usize) and store it in board and pass here.
-    fn get_all_on_board(r: usize, c: usize) -> Vec<Loc> {
+    pub fn get_all_on_board(r: usize, c: usize) -> Vec<Loc> {
         let mut all_loc: Vec<Loc> = vec![];
         // TODO: bug: you don't want to iterate over sentinels
         for row in 0..r {
@@ -140,40 +235,112 @@ impl Loc {
         }
         all_loc
     }
+}
 
-    pub fn pass() -> Self {
-        Loc { row: 99, col: 99 }
-    }
-
-    fn is_pass(&self) -> bool {
-        self.row == 99 && self.col == 99
-    }
+// Passes and resignations are kinds of moves of their own, so every point of any board size can be played
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveKind {
+    Play(Loc),
+    Pass,
+    Resign,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move {
     pub player: Player,
-    pub loc: Loc,
+    pub kind: MoveKind,
 }
 
 impl Move {
+    pub fn new(player: Player, loc: Loc) -> Self {
+        Move {
+            player,
+            kind: MoveKind::Play(loc),
+        }
+    }
+
+    // None for passes and resignations
+    pub fn get_loc(&self) -> Option<Loc> {
+        match self.kind {
+            MoveKind::Play(loc) => Some(loc),
+            MoveKind::Pass | MoveKind::Resign => None,
+        }
+    }
+
     pub fn pass(self) -> Self {
         Move {
             player: self.player,
-            loc: Loc { row: 99, col: 99 }, // TODO: if you happen to change the representation of pass to 98 98, this would be a bug. Please remember to reuse higher abstractions.
+            kind: MoveKind::Pass,
         }
     }
 
     pub fn is_pass(&self) -> bool {
-        // self.loc == Loc::pass()
-        match self.loc {
-            Loc { row: 99, col: 99 } => true,
-            _ => false,
+        self.kind == MoveKind::Pass
+    }
+
+    pub fn resign(self) -> Self {
+        Move {
+            player: self.player,
+            kind: MoveKind::Resign,
         }
     }
+
+    pub fn is_resign(&self) -> bool {
+        self.kind == MoveKind::Resign
+    }
 }
 
+// One bit per point, sizes include sentinels
 #[derive(Clone, PartialEq)]
+pub struct PointSet {
+    cols: usize,
+    words: Vec<u64>,
+}
+
+impl PointSet {
+    pub fn new(board_size: (usize, usize)) -> Self {
+        let (rows, cols) = board_size;
+        PointSet {
+            cols,
+            words: vec![0; (rows * cols).div_ceil(64)],
+        }
+    }
+
+    fn bit(&self, loc: Loc) -> (usize, u64) {
+        let index = loc.row * self.cols + loc.col;
+        (index / 64, 1 << (index % 64))
+    }
+
+    // False when the point was in the set already
+    pub fn insert(&mut self, loc: Loc) -> bool {
+        let (word, mask) = self.bit(loc);
+        let is_new = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        is_new
+    }
+
+    pub fn contains(&self, loc: Loc) -> bool {
+        let (word, mask) = self.bit(loc);
+        self.words[word] & mask != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "BoardSnapshot"))]
 pub struct Board {
     fields: Vec<Vec<Color>>,
     game_history: Vec<Move>,
@@ -181,6 +348,47 @@ pub struct Board {
     komi: f32,
     black_captures: isize,
     white_captures: isize,
+    // The position the game history starts from, None for an empty board
+    start: Option<Box<Board>>,
+    // Point the player to move may not take because it would retake a ko at once, kept up to date by every move
+    ko: Option<Loc>,
+    chains: Chains,
+}
+
+// A deserialized board before it is checked, snapshots may be hand-edited or hostile
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct BoardSnapshot {
+    fields: Vec<Vec<Color>>,
+    game_history: Vec<Move>,
+    current_player: Player,
+    komi: f32,
+    black_captures: isize,
+    white_captures: isize,
+    start: Option<Box<Board>>,
+    ko: Option<Loc>,
+    chains: Chains,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<BoardSnapshot> for Board {
+    type Error = String;
+
+    fn try_from(snapshot: BoardSnapshot) -> Result<Self, Self::Error> {
+        let board = Board {
+            fields: snapshot.fields,
+            game_history: snapshot.game_history,
+            current_player: snapshot.current_player,
+            komi: snapshot.komi,
+            black_captures: snapshot.black_captures,
+            white_captures: snapshot.white_captures,
+            start: snapshot.start,
+            ko: snapshot.ko,
+            chains: snapshot.chains,
+        };
+        board.check()?;
+        Ok(board)
+    }
 }
 
 impl Board {
@@ -194,6 +402,9 @@ impl Board {
             komi,
             black_captures: 0,
             white_captures: 0,
+            start: None,
+            ko: None,
+            chains: Chains::new(rows, cols),
         };
         // Setting up sentinels in rows
         // TODO a better algo would be to set sentinels everywhere and then iterate over Loc::all_on_board()
@@ -210,7 +421,14 @@ impl Board {
     }
 
     fn reset(&self) -> Self {
-        return Board::new(self.fields.len(), self.fields[0].len(), self.komi);
+        match &self.start {
+            Some(start) => {
+                let mut board = (**start).clone();
+                board.start = Some(start.clone());
+                board
+            }
+            None => Board::new(self.fields.len(), self.fields[0].len(), self.komi),
+        }
     }
 
     pub fn get_game_history(&self) -> &Vec<Move> {
@@ -225,40 +443,50 @@ impl Board {
         self.current_player = player;
     }
 
-    fn get(&self, loc: Loc) -> Color {
-        self.fields[loc.row][loc.col]
+    // Points outside the fields are Invalid like the sentinels
+    pub fn get(&self, loc: Loc) -> Color {
+        self.fields
+            .get(loc.row)
+            .and_then(|row| row.get(loc.col))
+            .copied()
+            .unwrap_or(Color::Invalid)
     }
 
     fn set(&mut self, loc: Loc, color: Color) {
         self.fields[loc.row][loc.col] = color;
     }
 
-    pub fn to_string(&self) -> String {
-        // TODO: add standard coordinates on sides.
-        let mut board_string = String::new();
-        for row in &self.fields {
-            for field in row {
-                board_string.push_str(&format!("{} ", field.to_string()));
-            }
-            board_string += "\n";
+    // Sets up a position without going through the rules, the stone is not recorded in the game history
+    pub fn place_stone(&mut self, loc: Loc, player: Player) {
+        if !loc.is_on_board(self.board_size()) {
+            return;
+        }
+        let was_empty = self.get(loc) == Color::Empty;
+        self.set(loc, player.to_color());
+        if was_empty {
+            self.chains.add_stone(loc, &self.fields);
+        } else {
+            self.chains = Chains::from_fields(&self.fields);
         }
-        board_string
     }
 
-    fn board_size(&self) -> (usize, usize) {
+    pub fn board_size(&self) -> (usize, usize) {
         (self.fields.len(), self.fields[0].len())
     }
 
     // Creates a set of potential points
-    fn empty_islands(&self) -> HashSet<Vec<Loc>> {
-        let mut islands: HashSet<Vec<Loc>> = HashSet::new();
+    fn empty_islands(&self) -> Vec<Vec<Loc>> {
+        let mut islands: Vec<Vec<Loc>> = vec![];
+        let mut seen = PointSet::new(self.board_size());
         let (rows, cols) = self.board_size();
-        for loc in Loc::get_all(rows, cols) {
-            if self.get(loc) == Color::Empty {
-                // If the group of Locs contains current Loc, the group of this loc has already been added
-                if !islands.iter().any(|group| group.contains(&loc)) {
-                    islands.insert(self.group_stones(loc));
+        for loc in Loc::get_all_on_board(rows, cols) {
+            // A seen Loc belongs to an island that has already been added
+            if self.get(loc) == Color::Empty && !seen.contains(loc) {
+                let island = self.region(loc);
+                for point in &island {
+                    seen.insert(*point);
                 }
+                islands.push(island);
             }
         }
         islands
@@ -307,7 +535,7 @@ impl Board {
 
     // Grouping empty "islands" and checking bordering Colors to decide which Color the points belong
     fn count_board_points(&self) -> (isize, isize) {
-        // Populating the HashSet of Empty "islands"
+        // Populating the list of Empty "islands"
         let groups_of_potential_points = self.empty_islands();
 
         let mut white_points: isize = 0;
@@ -326,10 +554,39 @@ impl Board {
         (black_points, white_points)
     }
 
+    // Who every point counts for as the position stands: the stone on it, or the only color around its empty region.
+    // Empty for dame, Invalid for sentinels
+    pub fn get_owners(&self) -> Vec<Vec<Color>> {
+        let mut owners = self.fields.clone();
+        for island in self.empty_islands() {
+            let (color, _) = self.count_potential_points(island[0]);
+            for loc in island {
+                owners[loc.row][loc.col] = color;
+            }
+        }
+        owners
+    }
+
+    // Stones and territory minus komi as the position stands, captures don't count
+    pub fn area_score(&self) -> GameResult {
+        if let Some(player) = self.resigned() {
+            return GameResult::Resignation(player.opponent());
+        }
+        let (mut black, mut white) = (0.0, 0.0);
+        for owner in self.get_owners().iter().flatten() {
+            match owner {
+                Color::Black => black += 1.0,
+                Color::White => white += 1.0,
+                _ => (),
+            }
+        }
+        GameResult::from_black_margin(black - white - self.komi)
+    }
+
     fn remove_dead_stones_for_counting(&mut self) {
         loop {
             println!("\nRemove dead stones or input 'r' to calculate the result:\n");
-            println!("{}", self.to_string());
+            println!("{}", self);
 
             let player_input = self::take_player_input();
             match player_input.as_str() {
@@ -346,7 +603,17 @@ impl Board {
     }
 
     pub fn count_score(&mut self) -> GameResult {
-        self.remove_dead_stones_for_counting();
+        if self.resigned().is_none() {
+            self.remove_dead_stones_for_counting();
+        }
+        self.final_score()
+    }
+
+    // Scores the position as it is, without asking about dead stones
+    pub fn final_score(&self) -> GameResult {
+        if let Some(player) = self.resigned() {
+            return GameResult::Resignation(player.opponent());
+        }
         let all_points = self.count_board_points();
         let black_total_points: f32 = all_points.0 as f32 + self.black_captures as f32;
         let white_total_points: f32 = all_points.1 as f32 + self.white_captures as f32 + self.komi;
@@ -356,15 +623,12 @@ impl Board {
         }
 
         let black_won = black_total_points > white_total_points;
-        let result: GameResult;
 
         if black_won {
-            result = GameResult::Player(Player::Black, black_total_points - white_total_points);
+            GameResult::Player(Player::Black, black_total_points - white_total_points)
         } else {
-            result = GameResult::Player(Player::White, white_total_points - black_total_points);
+            GameResult::Player(Player::White, white_total_points - black_total_points)
         }
-
-        result
     }
 
     #[allow(dead_code)]
@@ -372,60 +636,74 @@ impl Board {
         self.fields == board.fields
     }
 
-    #[allow(dead_code)]
-    fn move_is_valid(&self, mv: &Move) -> bool {
-        let board_size = self.board_size();
-        if !mv.loc.is_on_board(board_size) && !mv.loc.is_pass() {
+    pub fn move_is_valid(&self, mv: &Move) -> bool {
+        if self.is_over() {
             return false;
         }
-
-        if self.get(mv.loc) != Color::Empty {
+        let loc = match mv.kind {
+            MoveKind::Play(loc) => loc,
+            MoveKind::Pass | MoveKind::Resign => return true,
+        };
+        if self.ko == Some(loc) {
             return false;
         }
 
-        let mut potential_board = self.clone();
-        if potential_board.get(mv.loc) == Color::Empty {
-            potential_board.unsafe_play(mv);
-        } else {
+        let board_size = self.board_size();
+        if !loc.is_on_board(board_size) {
             return false;
         }
 
-        let mut gh_copy = self.game_history.clone();
-        gh_copy.pop();
-        let mut board_from_2_moves_ago = self.reset();
-        for mv in gh_copy {
-            board_from_2_moves_ago.play(&mv);
+        if self.get(loc) != Color::Empty {
+            return false;
         }
-        // If the group has been removed after the move, it was a suicidcal move
-        let move_is_suicidal = potential_board.get(mv.loc) == Color::Empty;
-        let board_is_repeated = board_from_2_moves_ago.board_position_is_reapated(potential_board);
 
-        !move_is_suicidal && !board_is_repeated
+        !self.is_suicide(mv.player, loc)
     }
 
-    fn unsafe_play(&mut self, mv: &Move) {
-        self.game_history.push(mv.clone());
+    // A move keeps a liberty when it has an empty neighbour, joins an own group with another liberty
+    // or captures an opponent group
+    fn is_suicide(&self, player: Player, loc: Loc) -> bool {
+        let own = player.to_color();
+        !loc.all_4nbr().into_iter().any(|nbr| match self.get(nbr) {
+            Color::Empty => true,
+            Color::Invalid => false,
+            color if color == own => self.count_liberties(nbr) > 1,
+            _ => self.count_liberties(nbr) == 1,
+        })
+    }
 
-        if mv.is_pass() {
-            self.current_player = self.current_player.opponent();
-            return;
-        }
+    fn unsafe_play(&mut self, mv: &Move) {
+        self.game_history.push(*mv);
+
+        self.ko = None;
+        let loc = match mv.kind {
+            MoveKind::Play(loc) => loc,
+            MoveKind::Pass | MoveKind::Resign => {
+                self.current_player = self.current_player.opponent();
+                return;
+            }
+        };
 
-        self.set(mv.loc, mv.player.to_color());
+        self.set(loc, mv.player.to_color());
+        self.chains.add_stone(loc, &self.fields);
         self.current_player = self.current_player.opponent();
 
-        // Remove dead groups
-        fn get_check_invalid_remove_group_combo(board: &mut Board, loc: Loc) {
-            let color = board.get(loc);
-            if color != Color::Invalid && color != Color::Empty && board.count_liberties(loc) == 0 {
-                board.remove_group(loc);
+        // Remove dead opponent groups
+        let opponent = mv.player.opponent().to_color();
+        let captures_before = self.get_captures(mv.player);
+        let mut captured = None;
+        for nbr in loc.all_4nbr() {
+            if self.get(nbr) == opponent && !self.chains.has_liberties(nbr) {
+                self.remove_group(nbr);
+                captured = Some(nbr);
             }
         }
 
-        get_check_invalid_remove_group_combo(self, mv.loc.up());
-        get_check_invalid_remove_group_combo(self, mv.loc.down());
-        get_check_invalid_remove_group_combo(self, mv.loc.left());
-        get_check_invalid_remove_group_combo(self, mv.loc.right());
+        // A single stone that took a single stone and has only that point as liberty can be taken back at once
+        let took_one = self.get_captures(mv.player) == captures_before + 1;
+        if took_one && self.chains.stones(loc).len() == 1 && self.count_liberties(loc) == 1 {
+            self.ko = captured;
+        }
     }
 
     #[allow(dead_code)]
@@ -436,50 +714,80 @@ impl Board {
         }
     }
 
-    fn group_stones(&self, loc: Loc) -> Vec<Loc> {
-        let mut group_stones_coordinates: Vec<Loc> = vec![];
-        let color = self.fields[loc.row][loc.col];
-        self.flood_fill(loc, color, &mut group_stones_coordinates);
+    pub fn group_stones(&self, loc: Loc) -> Vec<Loc> {
+        if self.chains.has_stone(loc) {
+            let mut stones = self.chains.stones(loc);
+            stones.sort();
+            return stones;
+        }
+        let mut group_stones_coordinates = self.region(loc);
         group_stones_coordinates.sort();
         group_stones_coordinates
     }
 
-    fn flood_fill(&self, loc: Loc, color: Color, visited: &mut Vec<Loc>) {
-        if visited.contains(&loc) {
-            return;
+    // Every point of the color of loc connected to it, with an explicit stack so
+    // a region as big as the board needs no deep recursion, sentinels and points off the board are regions of their own
+    pub fn region(&self, loc: Loc) -> Vec<Loc> {
+        let board_size = self.board_size();
+        if !loc.is_on_board(board_size) {
+            return vec![loc];
         }
-        if self.get(loc) != color {
-            return;
+        let color = self.get(loc);
+        let mut visited = PointSet::new(board_size);
+        let mut stack = vec![loc];
+        let mut region = vec![];
+        visited.insert(loc);
+        while let Some(point) = stack.pop() {
+            region.push(point);
+            for nbr in point.all_4nbr() {
+                if self.get(nbr) == color && visited.insert(nbr) {
+                    stack.push(nbr);
+                }
+            }
         }
+        region
+    }
 
-        visited.push(loc);
+    pub fn is_own_eye(&self, loc: Loc, player: Player) -> bool {
+        is_eye(loc, player.to_color(), |loc| self.get(loc))
+    }
 
-        self.flood_fill(loc.up(), color, visited);
-        self.flood_fill(loc.down(), color, visited);
-        self.flood_fill(loc.left(), color, visited);
-        self.flood_fill(loc.right(), color, visited);
+    pub fn count_liberties(&self, loc: Loc) -> usize {
+        self.get_liberties(loc).len()
     }
 
-    fn count_liberties(&self, loc: Loc) -> usize {
-        let group = self.group_stones(loc);
-        let mut liberties: HashSet<Loc> = HashSet::new();
-        fn get_check_empty_insert_combo(board: &Board, loc: Loc, liberties: &mut HashSet<Loc>) {
-            let color = board.get(loc);
-            if color == Color::Empty {
-                liberties.insert(loc);
+    // The empty points next to the group, or next to the region for empty points
+    pub fn get_liberties(&self, loc: Loc) -> Vec<Loc> {
+        if self.chains.has_stone(loc) {
+            return self.chains.liberties(loc, &self.fields);
+        }
+        let mut seen = PointSet::new(self.board_size());
+        let mut liberties = vec![];
+        for stone_coords in self.region(loc) {
+            if !stone_coords.is_on_board(self.board_size()) {
+                continue;
+            }
+            for nbr in stone_coords.all_4nbr() {
+                if self.get(nbr) == Color::Empty && seen.insert(nbr) {
+                    liberties.push(nbr);
+                }
             }
         }
-        for stone_coords in group {
-            get_check_empty_insert_combo(self, stone_coords.up(), &mut liberties);
-            get_check_empty_insert_combo(self, stone_coords.down(), &mut liberties);
-            get_check_empty_insert_combo(self, stone_coords.left(), &mut liberties);
-            get_check_empty_insert_combo(self, stone_coords.right(), &mut liberties);
+        liberties
+    }
+
+    pub fn get_captures(&self, player: Player) -> isize {
+        match player {
+            Player::Black => self.black_captures,
+            Player::White => self.white_captures,
         }
-        liberties.len()
     }
 
     pub fn remove_group(&mut self, loc: Loc) {
-        let group = self.group_stones(loc);
+        if !self.chains.has_stone(loc) {
+            return;
+        }
+        let group = self.chains.remove_chain(loc);
         let stone_count: isize = group.len().try_into().unwrap();
         match self.get(loc) {
             Color::White => self.black_captures += stone_count,
@@ -492,14 +800,14 @@ impl Board {
     }
 
     pub fn undo(mut self) -> Self {
-        if self.game_history.len() == 0 {
+        if self.game_history.is_empty() {
             return self;
         }
 
         let mut board_after_undo = self.reset();
         self.game_history.pop();
         for mv in &self.game_history {
-            board_after_undo.play(mv);
+            board_after_undo.unsafe_play(mv);
         }
         board_after_undo
     }
@@ -507,12 +815,311 @@ impl Board {
     pub fn last_two_moves_are_pass(&self) -> bool {
         if self.game_history.len() > 1 {
             let last_two_moves = &self.game_history[self.game_history.len() - 2..];
-            return last_two_moves[0].loc == last_two_moves[1].loc; 
-            // TODO this is close to incorrect and very unreadable
-            // 
+            return last_two_moves[0].is_pass() && last_two_moves[1].is_pass();
         }
         false
     }
+
+    // The player who resigned, resigning is always the last move of the game
+    pub fn resigned(&self) -> Option<Player> {
+        self.game_history
+            .last()
+            .filter(|mv| mv.is_resign())
+            .map(|mv| mv.player)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.last_two_moves_are_pass() || self.resigned().is_some()
+    }
+}
+
+// Diagrams show only the points on the board, O is Black, # is White like in Display.
+// Multi-line, with a header line:     One-line, rows split by | and the header after ;
+//     White to play, komi 6.5             O#.|.O.|...; White to play, komi 6.5
+//     O # .
+//     . O .
+//     . . .
+// Header parts are optional: "<Black|White> to play", "komi <k>", "captures <black> <white>", "ko <row> <col>"
+impl Board {
+    pub fn from_diagram(diagram: &str) -> Result<Self, String> {
+        let (body, header) = diagram.split_once(';').unwrap_or((diagram, ""));
+        let (rows, mut headers): (Vec<&str>, Vec<&str>) = body
+            .split(['\n', '|'])
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .partition(|line| !is_header(line));
+        headers.push(header);
+
+        let mut fields = vec![];
+        for row in rows {
+            let row: Vec<char> = row
+                .chars()
+                .filter(|c| !c.is_whitespace() && *c != '/')
+                .collect();
+            // Sentinel rows of Display output are skipped
+            if row.is_empty() {
+                continue;
+            }
+            let colors = row
+                .iter()
+                .map(|c| match c {
+                    'O' => Ok(Color::Black),
+                    '#' => Ok(Color::White),
+                    '.' => Ok(Color::Empty),
+                    c => Err(format!("Unknown point '{}'", c)),
+                })
+                .collect::<Result<Vec<Color>, String>>()?;
+            fields.push(colors);
+        }
+        let cols = fields
+            .first()
+            .map(|row| row.len())
+            .ok_or("The diagram has no rows")?;
+        if fields.iter().any(|row| row.len() != cols) {
+            return Err(String::from("All rows need the same number of points"));
+        }
+
+        let mut board = Board::new(fields.len() + 2, cols + 2, 0.0);
+        for (row, colors) in fields.iter().enumerate() {
+            for (col, color) in colors.iter().enumerate() {
+                board.set(
+                    Loc {
+                        row: row + 1,
+                        col: col + 1,
+                    },
+                    *color,
+                );
+            }
+        }
+        board.chains = Chains::from_fields(&board.fields);
+        for header in headers {
+            for part in header
+                .split(',')
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+            {
+                board.apply_header(part)?;
+            }
+        }
+
+        let (rows, cols) = board.board_size();
+        for loc in Loc::get_all_on_board(rows, cols) {
+            if matches!(board.get(loc), Color::Black | Color::White)
+                && board.count_liberties(loc) == 0
+            {
+                return Err(format!(
+                    "The group at {}, {} has no liberties",
+                    loc.row, loc.col
+                ));
+            }
+        }
+        if let Some(ko) = board.ko {
+            if !ko.is_on_board(board.board_size()) || board.get(ko) != Color::Empty {
+                return Err(format!(
+                    "The ko point {}, {} isn't an empty point",
+                    ko.row, ko.col
+                ));
+            }
+        }
+        board.start = Some(Box::new(board.clone()));
+        Ok(board)
+    }
+
+    fn apply_header(&mut self, part: &str) -> Result<(), String> {
+        let words: Vec<&str> = part.split_whitespace().collect();
+        let number = |i: usize| {
+            words
+                .get(i)
+                .and_then(|word| word.parse::<isize>().ok())
+                .filter(|number| *number >= 0)
+                .ok_or(format!("Expected a number of at least 0 in '{}'", part))
+        };
+        match words[..] {
+            ["Black", "to", "play"] => self.current_player = Player::Black,
+            ["White", "to", "play"] => self.current_player = Player::White,
+            ["komi", komi] => {
+                self.komi = komi
+                    .parse()
+                    .map_err(|_| format!("Invalid komi '{}'", komi))?
+            }
+            ["captures", _, _] => {
+                self.black_captures = number(1)?;
+                self.white_captures = number(2)?;
+            }
+            ["ko", _, _] => {
+                self.ko = Some(Loc {
+                    row: number(1)? as usize,
+                    col: number(2)? as usize,
+                })
+            }
+            _ => return Err(format!("Unknown header '{}'", part)),
+        }
+        Ok(())
+    }
+
+    // The same game on the transformed board, a position set up from a diagram is transformed as well
+    pub fn transform(&self, symmetry: Symmetry) -> Board {
+        let size = self.board_size();
+        let mut board = match &self.start {
+            Some(start) => {
+                let mut board = start.transform_position(symmetry);
+                board.start = Some(Box::new(board.clone()));
+                board
+            }
+            None => {
+                let (rows, cols) = symmetry.board_size(size);
+                Board::new(rows, cols, self.komi)
+            }
+        };
+        for mv in &self.game_history {
+            board.unsafe_play(&symmetry.apply_move(mv, size));
+        }
+        board
+    }
+
+    // The position as the start of a new game, undo can't go back further, ko carries over
+    pub fn without_history(&self) -> Board {
+        let mut board = self.transform_position(Symmetry::Identity);
+        board.start = Some(Box::new(board.clone()));
+        board
+    }
+
+    // The same game with nothing banned by ko, as after an exchange of ko threats elsewhere
+    pub fn without_ko(&self) -> Board {
+        let mut board = self.clone();
+        board.ko = None;
+        board
+    }
+
+    // The point the player to move may not play on because it would retake a ko at once
+    pub fn get_ko(&self) -> Option<Loc> {
+        self.ko
+    }
+
+    // Stones, captures, the player to move and ko, without the game history
+    // Everything the board takes for granted elsewhere: sentinels around the fields, chains that match
+    // the stones, and a history that undo can replay from the start to this very position
+    #[cfg(feature = "serde")]
+    fn check(&self) -> Result<(), String> {
+        let rows = self.fields.len();
+        let cols = self.fields.first().map_or(0, |row| row.len());
+        if rows < 3 || cols < 3 || self.fields.iter().any(|row| row.len() != cols) {
+            return Err(String::from("The fields are not a board with sentinels"));
+        }
+        for loc in Loc::get_all_on_board(rows, cols) {
+            if (self.get(loc) == Color::Invalid) == loc.is_on_board((rows, cols)) {
+                return Err(format!(
+                    "{}, {} doesn't fit the sentinels",
+                    loc.row, loc.col
+                ));
+            }
+        }
+        if !self.chains.agrees_with(&self.fields) {
+            return Err(String::from("The chains don't match the stones"));
+        }
+        if self.black_captures < 0 || self.white_captures < 0 {
+            return Err(String::from("Capture counts can't be negative"));
+        }
+        if let Some(ko) = self.ko {
+            if !ko.is_on_board((rows, cols)) || self.get(ko) != Color::Empty {
+                return Err(format!("The ko point {}, {} isn't empty", ko.row, ko.col));
+            }
+        }
+
+        let mut board = match &self.start {
+            Some(start) if start.board_size() == (rows, cols) && start.game_history.is_empty() => {
+                (**start).clone()
+            }
+            Some(_) => return Err(String::from("The start doesn't fit the board")),
+            None => Board::new(rows, cols, self.komi),
+        };
+        for mv in &self.game_history {
+            if let MoveKind::Play(loc) = mv.kind {
+                if !loc.is_on_board((rows, cols)) || board.get(loc) != Color::Empty {
+                    return Err(format!("{}, {} can't be replayed", loc.row, loc.col));
+                }
+            }
+            board.unsafe_play(mv);
+        }
+        // Without a history undo leaves the board as it is, set up stones don't have to follow from the start
+        let follows = self.game_history.is_empty()
+            || (board.fields == self.fields
+                && board.black_captures == self.black_captures
+                && board.white_captures == self.white_captures
+                && board.ko == self.ko
+                && board.current_player == self.current_player);
+        if !follows {
+            return Err(String::from(
+                "The position doesn't follow from the game history",
+            ));
+        }
+        Ok(())
+    }
+
+    fn transform_position(&self, symmetry: Symmetry) -> Board {
+        let size = self.board_size();
+        let (rows, cols) = symmetry.board_size(size);
+        let mut board = Board::new(rows, cols, self.komi);
+        for loc in Loc::get_all_on_board(size.0, size.1) {
+            board.set(symmetry.apply(loc, size), self.get(loc));
+        }
+        board.chains = Chains::from_fields(&board.fields);
+        board.current_player = self.current_player;
+        board.black_captures = self.black_captures;
+        board.white_captures = self.white_captures;
+        board.ko = self.ko.map(|ko| symmetry.apply(ko, size));
+        board
+    }
+
+    // The multi-line diagram of the position
+    pub fn to_diagram(&self) -> String {
+        let mut header = format!("{:?} to play, komi {}", self.current_player, self.komi);
+        if self.black_captures != 0 || self.white_captures != 0 {
+            header += &format!(", captures {} {}", self.black_captures, self.white_captures);
+        }
+        if let Some(ko) = self.ko {
+            header += &format!(", ko {} {}", ko.row, ko.col);
+        }
+
+        let (rows, cols) = self.board_size();
+        let mut diagram = header + "\n";
+        for row in 1..rows - 1 {
+            let points: Vec<String> = (1..cols - 1)
+                .map(|col| self.get(Loc { row, col }).to_string())
+                .collect();
+            diagram += &points.join(" ");
+            diagram += "\n";
+        }
+        diagram
+    }
+}
+
+// An empty point surrounded only by stones of the own color and edges, as get sees the points,
+// so players' views with their own idea of the board can ask too
+pub fn is_eye(loc: Loc, own: Color, get: impl Fn(Loc) -> Color) -> bool {
+    get(loc) == Color::Empty
+        && loc
+            .all_4nbr()
+            .into_iter()
+            .all(|nbr| get(nbr) == own || get(nbr) == Color::Invalid)
+}
+
+// Board rows hold only O, #, ., / and spaces, so any other letter starts a header
+fn is_header(line: &str) -> bool {
+    line.chars().any(|c| c.is_alphabetic() && c != 'O')
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // TODO: add standard coordinates on sides.
+        for row in &self.fields {
+            for field in row {
+                write!(f, "{} ", field)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 pub fn take_player_input() -> String {
@@ -530,90 +1137,47 @@ mod tests {
 
     use crate::board::Board;
     use crate::board::Color;
+    use crate::board::GameResult;
     use crate::board::Loc;
     use crate::board::Move;
+    use crate::board::MoveKind;
     use crate::board::Player;
+    use crate::board::PointSet;
 
     #[test]
     fn stones_have_to_be_placed_on_empty_fields() {
         let mut board = Board::new(5, 5, 0.0);
         assert_eq!(board.get(Loc { row: 1, col: 1 }), Color::Empty);
-        board.play(&Move {
-            player: Player::Black,
-            loc: Loc { row: 1, col: 1 },
-        });
+        board.play(&Move::new(Player::Black, Loc { row: 1, col: 1 }));
 
         assert_eq!(board.get(Loc { row: 1, col: 1 }), Color::Black);
-        board.play(&Move {
-            player: Player::White,
-            loc: Loc { row: 1, col: 1 },
-        });
+        board.play(&Move::new(Player::White, Loc { row: 1, col: 1 }));
 
         assert_eq!(board.get(Loc { row: 1, col: 1 }), Color::Black);
         assert_eq!(board.get(Loc { row: 1, col: 2 }), Color::Empty);
-        board.play(&Move {
-            player: Player::White,
-            loc: Loc { row: 1, col: 2 },
-        });
+        board.play(&Move::new(Player::White, Loc { row: 1, col: 2 }));
         assert_eq!(board.get(Loc { row: 1, col: 1 }), Color::Black);
         assert_eq!(board.get(Loc { row: 1, col: 2 }), Color::White);
-        board.play(&Move {
-            player: Player::Black,
-            loc: Loc { row: 1, col: 2 },
-        });
+        board.play(&Move::new(Player::Black, Loc { row: 1, col: 2 }));
         assert_eq!(board.get(Loc { row: 1, col: 1 }), Color::Black);
         assert_eq!(board.get(Loc { row: 1, col: 2 }), Color::White);
     }
 
     #[test]
     fn stones_are_grouped_correctly() {
-        let mut board = Board::new(11, 11, 2.0);
-
-        let black_groups: Vec<Loc> = vec![
-            // Group 1
-            Loc::from_string("1, 1").expect("Failed to create Loc from string"),
-            Loc { row: 1, col: 2 },
-            // Group 2
-            Loc { row: 4, col: 1 },
-            Loc { row: 5, col: 1 },
-            // Group 3
-            Loc { row: 3, col: 3 },
-            Loc { row: 3, col: 4 },
-            Loc { row: 4, col: 3 },
-            // Group 4
-            Loc { row: 4, col: 7 },
-            Loc { row: 5, col: 7 },
-            Loc { row: 6, col: 7 },
-        ];
-        let white_groups: Vec<Loc> = vec![
-            // Group 5
-            Loc { row: 2, col: 2 },
-            Loc { row: 3, col: 1 },
-            Loc { row: 3, col: 2 },
-            Loc { row: 4, col: 2 },
-            // Group 6
-            Loc { row: 9, col: 1 },
-            // Group 7
-            Loc { row: 6, col: 2 },
-            Loc { row: 6, col: 3 },
-            Loc { row: 7, col: 2 },
-            Loc { row: 7, col: 3 },
-            Loc { row: 8, col: 2 },
-        ];
-
-        for mv in black_groups {
-            board.play(&Move {
-                player: Player::Black,
-                loc: mv,
-            })
-        }
-
-        for mv in white_groups {
-            board.play(&Move {
-                player: Player::White,
-                loc: mv,
-            })
-        }
+        let board = Board::from_diagram(
+            "komi 2
+             O O . . . . . . .
+             . # . . . . . . .
+             # # O O . . . . .
+             O # O . . . O . .
+             O . . . . . O . .
+             . # # . . . O . .
+             . # # . . . . . .
+             . # . . . . . . .
+             # . . . . . . . .",
+        )
+        .expect("Failed to read the diagram");
 
         let group1_a = board.group_stones(Loc { row: 1, col: 1 });
         let group1_b = board.group_stones(Loc { row: 1, col: 2 });
@@ -813,17 +1377,11 @@ mod tests {
         ];
 
         for mv in black_groups {
-            board.play(&Move {
-                player: Player::Black,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::Black, mv))
         }
 
         for mv in white_groups {
-            board.play(&Move {
-                player: Player::White,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::White, mv))
         }
 
         assert!(board.count_liberties(Loc { row: 1, col: 1 }) == 2);
@@ -885,17 +1443,11 @@ mod tests {
         ];
 
         for mv in black_groups {
-            board.play(&Move {
-                player: Player::Black,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::Black, mv))
         }
 
         for mv in white_groups {
-            board.play(&Move {
-                player: Player::White,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::White, mv))
         }
 
         board.remove_group(Loc { row: 1, col: 1 });
@@ -996,19 +1548,13 @@ mod tests {
 
         for group in &black_groups {
             for mv in group {
-                board.play(&Move {
-                    player: Player::Black,
-                    loc: *mv,
-                });
+                board.play(&Move::new(Player::Black, *mv));
             }
         }
 
         for (group_index, white_moves) in white_groups.iter().enumerate() {
             for (i, mv) in white_moves.iter().enumerate() {
-                board.play(&Move {
-                    player: Player::White,
-                    loc: *mv,
-                });
+                board.play(&Move::new(Player::White, *mv));
                 if i + 1 == white_moves.len() {
                     for loc in &black_groups[group_index] {
                         assert!(board.get(*loc) == Color::Empty);
@@ -1022,14 +1568,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_capture_removes_only_opponent_groups() {
+        // Black's corner group has no liberty until the White stone on 1, 3 is taken
+        let mut board = Board::new(5, 5, 0.5);
+        for (row, col) in [(1, 1), (2, 1), (2, 3), (2, 2), (3, 3), (1, 3), (1, 2)] {
+            board.play(&Move::new(board.get_current_player(), Loc { row, col }));
+        }
+        assert!(board.get(Loc { row: 1, col: 1 }) == Color::Black);
+        assert!(board.get(Loc { row: 1, col: 2 }) == Color::Black);
+        assert!(board.get(Loc { row: 1, col: 3 }) == Color::Empty);
+        assert_eq!(board.get_captures(Player::Black), 1);
+    }
+
+    #[test]
+    fn suicide_is_not_a_valid_move() {
+        let mut board = Board::new(5, 5, 0.5);
+        for (row, col) in [(1, 2), (3, 3), (2, 1)] {
+            board.play(&Move::new(board.get_current_player(), Loc { row, col }));
+        }
+        let suicide = Move::new(Player::White, Loc { row: 1, col: 1 });
+        assert!(!board.move_is_valid(&suicide));
+        board.play(&suicide);
+        assert!(board.get(Loc { row: 1, col: 1 }) == Color::Empty);
+        assert_eq!(board.get_current_player(), Player::White);
+    }
+
     #[test]
     fn undoing_multiple_moves_one_after_another_and_continuing_the_game_after() {
         let mut test_move_history: Vec<Move> = vec![];
         let mut rng = rand::thread_rng();
-        let mut current_move = Move {
-            player: Player::White,
-            loc: Loc { row: 0, col: 0 },
-        };
+        let mut current_move = Move::new(Player::White, Loc { row: 0, col: 0 });
 
         let mut board = Board::new(7, 7, 2.0);
         let mut moves_left = 10;
@@ -1038,10 +1607,10 @@ mod tests {
             let row = rng.gen_range(0..7);
             let col = rng.gen_range(0..7);
             let current_move_coords = Loc { row, col };
-            current_move.loc = current_move_coords;
+            current_move.kind = MoveKind::Play(current_move_coords);
 
             if board.move_is_valid(&current_move) {
-                test_move_history.push(current_move.clone());
+                test_move_history.push(current_move);
                 board.play(&current_move);
                 current_move.player = current_move.player.opponent();
                 moves_left -= 1;
@@ -1050,11 +1619,11 @@ mod tests {
 
         for _ in 1..=6 {
             let last_move = test_move_history.pop().unwrap();
-            assert_ne!(board.get(last_move.loc), Color::Empty);
+            assert_ne!(board.get(last_move.get_loc().unwrap()), Color::Empty);
 
             board = board.undo();
 
-            assert_eq!(board.get(last_move.loc), Color::Empty);
+            assert_eq!(board.get(last_move.get_loc().unwrap()), Color::Empty);
         }
 
         moves_left = 6;
@@ -1063,12 +1632,12 @@ mod tests {
             let row = rng.gen_range(0..7);
             let col = rng.gen_range(0..7);
             let current_move_coords = Loc { row, col };
-            current_move.loc = current_move_coords;
+            current_move.kind = MoveKind::Play(current_move_coords);
 
             if board.move_is_valid(&current_move) {
-                assert_eq!(board.get(current_move.loc), Color::Empty);
+                assert_eq!(board.get(current_move_coords), Color::Empty);
                 board.play(&current_move);
-                assert_ne!(board.get(current_move.loc), Color::Empty);
+                assert_ne!(board.get(current_move_coords), Color::Empty);
                 current_move.player = current_move.player.opponent();
 
                 println!();
@@ -1082,42 +1651,15 @@ mod tests {
         let mut board = Board::new(7, 5, 2.0);
 
         let moves = [
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 1, col: 1 },
-            },
-            Move {
-                player: Player::White,
-                loc: Loc { row: 1, col: 2 },
-            },
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 2, col: 1 },
-            },
-            Move {
-                player: Player::White,
-                loc: Loc { row: 2, col: 2 },
-            },
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 3, col: 2 },
-            },
-            Move {
-                player: Player::White,
-                loc: Loc { row: 3, col: 1 },
-            },
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 4, col: 1 },
-            },
-            Move {
-                player: Player::White,
-                loc: Loc { row: 4, col: 2 },
-            },
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 2, col: 1 },
-            },
+            Move::new(Player::Black, Loc { row: 1, col: 1 }),
+            Move::new(Player::White, Loc { row: 1, col: 2 }),
+            Move::new(Player::Black, Loc { row: 2, col: 1 }),
+            Move::new(Player::White, Loc { row: 2, col: 2 }),
+            Move::new(Player::Black, Loc { row: 3, col: 2 }),
+            Move::new(Player::White, Loc { row: 3, col: 1 }),
+            Move::new(Player::Black, Loc { row: 4, col: 1 }),
+            Move::new(Player::White, Loc { row: 4, col: 2 }),
+            Move::new(Player::Black, Loc { row: 2, col: 1 }),
         ];
 
         for mv in moves {
@@ -1136,72 +1678,33 @@ mod tests {
         let mut board = Board::new(6, 5, 2.0);
 
         let moves = [
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 3, col: 1 },
-            },
-            Move {
-                player: Player::White,
-                loc: Loc { row: 2, col: 1 },
-            },
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 2, col: 2 },
-            },
-            Move {
-                player: Player::White,
-                loc: Loc { row: 1, col: 2 },
-            },
-            Move {
-                player: Player::Black,
-                loc: Loc { row: 1, col: 1 },
-            },
+            Move::new(Player::Black, Loc { row: 3, col: 1 }),
+            Move::new(Player::White, Loc { row: 2, col: 1 }),
+            Move::new(Player::Black, Loc { row: 2, col: 2 }),
+            Move::new(Player::White, Loc { row: 1, col: 2 }),
+            Move::new(Player::Black, Loc { row: 1, col: 1 }),
         ];
 
         for mv in moves {
             board.play(&mv);
         }
 
-        board.play(&Move {
-            player: Player::White,
-            loc: Loc { row: 2, col: 1 },
-        });
+        board.play(&Move::new(Player::White, Loc { row: 2, col: 1 }));
 
         assert!(board.get(Loc { row: 2, col: 1 }) == Color::Empty);
         assert!(board.get(Loc { row: 1, col: 1 }) == Color::Black);
 
-        board.play(&Move {
-            player: Player::White,
-            loc: Loc { row: 4, col: 3 },
-        });
-        board.play(&Move {
-            player: Player::Black,
-            loc: Loc { row: 3, col: 3 },
-        });
-        board.play(&Move {
-            player: Player::White,
-            loc: Loc { row: 2, col: 1 },
-        });
-        board.play(&Move {
-            player: Player::Black,
-            loc: Loc { row: 1, col: 1 },
-        });
+        board.play(&Move::new(Player::White, Loc { row: 4, col: 3 }));
+        board.play(&Move::new(Player::Black, Loc { row: 3, col: 3 }));
+        board.play(&Move::new(Player::White, Loc { row: 2, col: 1 }));
+        board.play(&Move::new(Player::Black, Loc { row: 1, col: 1 }));
 
         assert!(board.get(Loc { row: 1, col: 1 }) == Color::Empty);
         assert!(board.get(Loc { row: 2, col: 1 }) == Color::White);
 
-        board.play(&Move {
-            player: Player::Black,
-            loc: Loc { row: 2, col: 3 },
-        });
-        board.play(&Move {
-            player: Player::White,
-            loc: Loc { row: 4, col: 2 },
-        });
-        board.play(&Move {
-            player: Player::Black,
-            loc: Loc { row: 1, col: 1 },
-        });
+        board.play(&Move::new(Player::Black, Loc { row: 2, col: 3 }));
+        board.play(&Move::new(Player::White, Loc { row: 4, col: 2 }));
+        board.play(&Move::new(Player::Black, Loc { row: 1, col: 1 }));
 
         assert!(board.get(Loc { row: 2, col: 1 }) == Color::Empty);
         assert!(board.get(Loc { row: 1, col: 1 }) == Color::Black);
@@ -1236,17 +1739,11 @@ mod tests {
         ];
 
         for mv in black_groups {
-            board.play(&Move {
-                player: Player::Black,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::Black, mv))
         }
 
         for mv in white_groups {
-            board.play(&Move {
-                player: Player::White,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::White, mv))
         }
 
         let loc_of_points_to_calculate = [
@@ -1323,17 +1820,11 @@ mod tests {
         ];
 
         for mv in black_groups {
-            board.play(&Move {
-                player: Player::Black,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::Black, mv))
         }
 
         for mv in white_groups {
-            board.play(&Move {
-                player: Player::White,
-                loc: mv,
-            })
+            board.play(&Move::new(Player::White, mv))
         }
 
         assert_eq!(board.count_board_points(), (3, 8));
@@ -1341,24 +1832,116 @@ mod tests {
 
     #[test]
     fn passing_works() {
-        let mut current_move = Move {
-            player: Player::Black,
-            loc: Loc { row: 1, col: 1 },
-        };
-        let expected_move = current_move.clone();
+        let mut current_move = Move::new(Player::Black, Loc { row: 1, col: 1 });
+        let expected_move = current_move;
         assert_eq!(current_move, expected_move);
 
         current_move = current_move.pass();
         assert!(current_move.is_pass());
 
-        current_move = Move {
-            player: Player::White,
-            loc: Loc { row: 5, col: 3 },
-        };
+        current_move = Move::new(Player::White, Loc { row: 5, col: 3 });
 
         current_move = current_move.pass();
         assert!(current_move.is_pass());
     }
+
+    #[test]
+    fn resigning_ends_the_game_and_the_opponent_wins() {
+        let mut board = Board::new(5, 5, 0.5);
+        let mv = Move::new(Player::Black, Loc { row: 1, col: 1 });
+        board.play(&mv);
+        assert!(!board.is_over());
+
+        board.play(&Move {
+            player: Player::White,
+            kind: MoveKind::Resign,
+        });
+        assert!(board.get_game_history().last().unwrap().is_resign());
+        assert_eq!(board.resigned(), Some(Player::White));
+        assert!(board.is_over());
+        assert!(!board.move_is_valid(&Move::new(Player::Black, Loc { row: 2, col: 2 })));
+        assert_eq!(board.final_score(), GameResult::Resignation(Player::Black));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn boards_and_results_round_trip_through_json() {
+        let mut board = Board::new(5, 5, 6.5);
+        board.play(&Move::new(Player::Black, Loc { row: 2, col: 2 }));
+        let json = serde_json::to_string(&board).unwrap();
+        assert!(json.contains(
+            "\"game_history\":[{\"player\":\"Black\",\"kind\":{\"Play\":{\"row\":2,\"col\":2}}}]"
+        ));
+        let restored: Board = serde_json::from_str(&json).unwrap();
+        assert!(restored == board);
+
+        let result = GameResult::Player(Player::White, 6.5);
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(serde_json::from_str::<GameResult>(&json).unwrap(), result);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tampered_board_snapshots_are_rejected() {
+        let mut board = Board::new(5, 5, 6.5);
+        for loc in [(2, 2), (2, 3), (3, 2)] {
+            board.play(&Move::new(
+                board.get_current_player(),
+                Loc {
+                    row: loc.0,
+                    col: loc.1,
+                },
+            ));
+        }
+        let snapshot = serde_json::to_value(&board).unwrap();
+        let tampered = |edit: &dyn Fn(&mut serde_json::Value)| {
+            let mut snapshot = snapshot.clone();
+            edit(&mut snapshot);
+            serde_json::from_value::<Board>(snapshot)
+        };
+        assert!(tampered(&|_| ()).is_ok());
+        assert!(tampered(&|board| {
+            board["fields"].as_array_mut().unwrap().pop();
+        })
+        .is_err());
+        // A stone without a chain
+        assert!(tampered(&|board| board["fields"][1][1] = "Black".into()).is_err());
+        assert!(tampered(&|board| board["chains"]["next"][2 * 5 + 2] = 0.into()).is_err());
+        assert!(tampered(&|board| board["black_captures"] = (-1).into()).is_err());
+        assert!(tampered(&|board| board["ko"] = serde_json::json!({"row": 2, "col": 2})).is_err());
+        assert!(
+            tampered(&|board| board["game_history"][2]["kind"]["Play"]["row"] = 9.into()).is_err()
+        );
+        // Positions that undo would jump away from
+        assert!(tampered(&|board| {
+            board["game_history"].as_array_mut().unwrap().pop();
+        })
+        .is_err());
+        assert!(tampered(&|board| board["white_captures"] = 2.into()).is_err());
+        assert!(tampered(&|board| board["current_player"] = "Black".into()).is_err());
+    }
+
+    #[test]
+    fn results_are_written_and_read_as_sgf() {
+        let results = [
+            (GameResult::Player(Player::Black, 3.5), "B+3.5"),
+            (GameResult::Time(Player::White), "W+T"),
+            (GameResult::Resignation(Player::Black), "B+R"),
+            (GameResult::Forfeit(Player::Black), "B+F"),
+            (GameResult::Draw, "0"),
+            (GameResult::Void, "Void"),
+        ];
+        for (result, re) in results {
+            assert_eq!(result.to_sgf(), re);
+            assert_eq!(GameResult::from_sgf(re), Some(result));
+        }
+        assert_eq!(
+            GameResult::from_sgf("W+Resign"),
+            Some(GameResult::Resignation(Player::White))
+        );
+        assert_eq!(GameResult::from_sgf("B+"), None);
+    }
+
     #[test]
     fn counting_captures() {
         let mut board = Board::new(8, 8, 0.0);
@@ -1397,43 +1980,345 @@ mod tests {
         ];
 
         for loc in black_groups {
-            board.play(&Move {
-                player: Player::Black,
-                loc,
-            });
+            board.play(&Move::new(Player::Black, loc));
         }
 
         assert_eq!(board.white_captures, 0);
         assert_eq!(board.black_captures, 0);
 
         for loc in white_capture_1 {
-            board.play(&Move {
-                player: Player::White,
-                loc,
-            });
+            board.play(&Move::new(Player::White, loc));
         }
 
         assert_eq!(board.white_captures, 1);
         assert_eq!(board.black_captures, 0);
 
         for loc in white_capture_2 {
-            board.play(&Move {
-                player: Player::White,
-                loc,
-            });
+            board.play(&Move::new(Player::White, loc));
         }
 
         assert_eq!(board.white_captures, 3);
         assert_eq!(board.black_captures, 0);
 
         for loc in white_capture_3 {
-            board.play(&Move {
-                player: Player::White,
-                loc,
-            });
+            board.play(&Move::new(Player::White, loc));
         }
 
         assert_eq!(board.white_captures, 8);
         assert_eq!(board.black_captures, 0);
     }
+
+    #[test]
+    fn moves_remove_only_opponent_groups() {
+        // Black at 1, 1 has no liberty of its own, but it takes the last liberty of White's stone at 1, 2
+        let mut board = Board::from_diagram(". # O|# O .|. . .").unwrap();
+        let take = Move::new(Player::Black, Loc { row: 1, col: 1 });
+        assert!(board.move_is_valid(&take));
+        board.play(&take);
+        assert_eq!(board.get(Loc { row: 1, col: 1 }), Color::Black);
+        assert_eq!(board.get(Loc { row: 1, col: 2 }), Color::Empty);
+        assert_eq!(board.get(Loc { row: 2, col: 1 }), Color::White);
+        assert_eq!(board.get_captures(Player::Black), 1);
+
+        // Without a capture the same kind of move is suicide and changes nothing
+        let board = Board::from_diagram(". . .|# O .|. # .").unwrap();
+        let suicide = Move::new(Player::Black, Loc { row: 3, col: 1 });
+        assert!(!board.move_is_valid(&suicide));
+        let mut played = board.clone();
+        played.play(&suicide);
+        assert!(played == board);
+    }
+
+    #[test]
+    fn diagrams_are_read_and_written() {
+        let board =
+            Board::from_diagram("O#.|.O.|...; White to play, komi 6.5, captures 1 2, ko 2 1")
+                .unwrap();
+        assert_eq!(board.get(Loc { row: 1, col: 2 }), Color::White);
+        assert_eq!(board.get_current_player(), Player::White);
+        assert_eq!(
+            board.to_diagram(),
+            "White to play, komi 6.5, captures 1 2, ko 2 1\nO # .\n. O .\n. . .\n"
+        );
+        let again = Board::from_diagram(&board.to_diagram()).unwrap();
+        assert_eq!(again.to_diagram(), board.to_diagram());
+        // Display output with its sentinels reads back as well
+        assert_eq!(
+            Board::from_diagram(&board.to_string())
+                .unwrap()
+                .get(Loc { row: 2, col: 2 }),
+            Color::Black
+        );
+    }
+
+    #[test]
+    fn the_ko_point_of_a_diagram_cant_be_retaken_at_once() {
+        let mut board = Board::from_diagram(
+            ". # O .
+             # O . O
+             . # O .; White to play, ko 2 3",
+        )
+        .unwrap();
+        let retake = Move::new(Player::White, Loc { row: 2, col: 3 });
+        assert!(!board.move_is_valid(&retake));
+        board.play(&retake.pass());
+        board.play(&Move::new(Player::Black, Loc { row: 3, col: 4 }));
+        assert!(board.move_is_valid(&retake));
+        board = board.undo().undo();
+        assert!(!board.move_is_valid(&retake));
+        assert_eq!(board.get(Loc { row: 2, col: 2 }), Color::Black);
+    }
+
+    #[test]
+    fn the_ko_of_the_last_move_carries_over_without_the_history() {
+        let mut board = Board::from_diagram(". # O .|# . # O|. # O .").unwrap();
+        board.play(&Move::new(Player::Black, Loc { row: 2, col: 2 }));
+        let retake = Move::new(Player::White, Loc { row: 2, col: 3 });
+        assert!(!board.move_is_valid(&retake));
+        assert!(!board.without_history().move_is_valid(&retake));
+    }
+
+    #[test]
+    fn eyes_are_empty_points_between_own_stones_and_edges() {
+        let board = Board::from_diagram(
+            ". O . #
+             O . O #
+             . O # .",
+        )
+        .unwrap();
+        assert!(board.is_own_eye(Loc { row: 1, col: 1 }, Player::Black));
+        assert!(board.is_own_eye(Loc { row: 2, col: 2 }, Player::Black));
+        assert!(!board.is_own_eye(Loc { row: 2, col: 2 }, Player::White));
+        assert!(board.is_own_eye(Loc { row: 3, col: 4 }, Player::White));
+        // Points next to both colors and stones are no eyes
+        assert!(!board.is_own_eye(Loc { row: 1, col: 3 }, Player::Black));
+        assert!(!board.is_own_eye(Loc { row: 1, col: 2 }, Player::Black));
+    }
+
+    #[test]
+    fn taking_a_ko_bans_the_retake_for_one_move() {
+        let mut board = Board::from_diagram(
+            ". # O . .
+             # O . O .
+             . # O . .; White to play",
+        )
+        .unwrap();
+        let take = Move::new(Player::White, Loc { row: 2, col: 3 });
+        let retake = Move::new(Player::Black, Loc { row: 2, col: 2 });
+        board.play(&take);
+        assert_eq!(board.get_ko(), retake.get_loc());
+        assert!(!board.move_is_valid(&retake));
+        assert!(board.without_ko().move_is_valid(&retake));
+        board.play(&Move::new(Player::Black, Loc { row: 3, col: 5 }));
+        board.play(&take.pass());
+        assert_eq!(board.get_ko(), None);
+        assert!(board.move_is_valid(&retake));
+    }
+
+    #[test]
+    fn broken_diagrams_are_rejected() {
+        assert!(Board::from_diagram("O.|..|.").is_err());
+        assert!(Board::from_diagram("O#|#.").is_err());
+        assert!(Board::from_diagram("OX|..").is_err());
+        assert!(Board::from_diagram("O.|..; ko 1 1").is_err());
+        assert!(Board::from_diagram("O.|..; komi six").is_err());
+        assert!(Board::from_diagram("O.|..; captures -1 0").is_err());
+        assert!(Board::from_diagram("O.|..; captures 1 -2").is_err());
+    }
+
+    #[test]
+    fn regions_as_big_as_the_board_need_no_deep_stack() {
+        // A 200x200 empty board is one island of 40000 points, far too deep for recursion on a small stack
+        let result = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(|| {
+                let mut board = Board::new(202, 202, 0.5);
+                board.play(&Move::new(Player::Black, Loc { row: 100, col: 100 }));
+                (
+                    board.region(Loc { row: 1, col: 1 }).len(),
+                    board.final_score(),
+                )
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(result.0, 200 * 200 - 1);
+        assert!(result.1 == GameResult::Player(Player::Black, 200.0 * 200.0 - 1.5));
+    }
+
+    #[test]
+    fn untrusted_locations_are_rejected_without_panicking() {
+        let mut board = Board::new(5, 5, 0.5);
+        board.place_stone(Loc { row: 1, col: 1 }, Player::White);
+        for text in [
+            "0, 0",
+            "4, 4",
+            "0, 9",
+            "18446744073709551615, 18446744073709551615",
+        ] {
+            let loc = Loc::from_string(text).unwrap();
+            let mv = Move::new(Player::Black, loc);
+            assert!(!board.move_is_valid(&mv));
+            board.play(&mv);
+            board.place_stone(loc, Player::Black);
+            board.remove_group(loc);
+            assert_eq!(board.get(loc), Color::Invalid);
+            assert_eq!(board.count_liberties(loc), 0);
+            assert_eq!(board.group_stones(loc), [loc]);
+        }
+        assert!(board.get_game_history().is_empty());
+        assert_eq!(board.count_liberties(Loc { row: 1, col: 1 }), 2);
+    }
+
+    #[test]
+    fn point_sets_hold_each_point_once() {
+        let mut set = PointSet::new((21, 21));
+        assert!(set.is_empty());
+        assert!(set.insert(Loc { row: 19, col: 19 }));
+        assert!(!set.insert(Loc { row: 19, col: 19 }));
+        assert!(set.insert(Loc { row: 0, col: 0 }));
+        assert!(set.contains(Loc { row: 19, col: 19 }) && !set.contains(Loc { row: 19, col: 18 }));
+        assert_eq!(set.len(), 2);
+    }
+}
+
+// Rules invariants over random legal games, attempts that are illegal are skipped
+#[cfg(test)]
+mod properties {
+    use crate::board::{Board, Color, Loc, Move, MoveKind, Player};
+    use proptest::prelude::*;
+
+    // Board sizes without sentinels and attempts as points on them, None is a pass
+    fn games() -> impl Strategy<Value = (usize, Vec<Option<(usize, usize)>>)> {
+        (2usize..8).prop_flat_map(|size| {
+            let attempt = prop_oneof![
+                1 => Just(None),
+                12 => (1..=size, 1..=size).prop_map(Some),
+            ];
+            (Just(size), prop::collection::vec(attempt, 0..150))
+        })
+    }
+
+    fn next_move(board: &Board, attempt: Option<(usize, usize)>) -> Move {
+        let kind = match attempt {
+            Some((row, col)) => MoveKind::Play(Loc { row, col }),
+            None => MoveKind::Pass,
+        };
+        Move {
+            player: board.get_current_player(),
+            kind,
+        }
+    }
+
+    fn points(board: &Board) -> Vec<Loc> {
+        let (rows, cols) = board.board_size();
+        Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| loc.is_on_board((rows, cols)))
+            .collect()
+    }
+
+    fn count_stones(board: &Board, player: Player) -> usize {
+        points(board)
+            .into_iter()
+            .filter(|loc| board.get(*loc) == player.to_color())
+            .count()
+    }
+
+    // Calls check with the board before and after every legal move of the game
+    fn play_game(
+        size: usize,
+        attempts: &[Option<(usize, usize)>],
+        mut check: impl FnMut(&Board, &Board, &Move),
+    ) -> Board {
+        let mut board = Board::new(size + 2, size + 2, 0.0);
+        for attempt in attempts {
+            let mv = next_move(&board, *attempt);
+            if board.is_over() {
+                break;
+            }
+            if !board.move_is_valid(&mv) {
+                continue;
+            }
+            let before = board.clone();
+            board.play(&mv);
+            check(&before, &board, &mv);
+        }
+        board
+    }
+
+    fn flipped(board: &Board) -> Board {
+        let (rows, cols) = board.board_size();
+        let mut diagram = format!(
+            "komi 0, captures {} {}\n",
+            board.get_captures(Player::White),
+            board.get_captures(Player::Black)
+        );
+        for row in 1..rows - 1 {
+            for col in 1..cols - 1 {
+                diagram.push(match board.get(Loc { row, col }) {
+                    Color::Black => '#',
+                    Color::White => 'O',
+                    _ => '.',
+                });
+            }
+            diagram.push('\n');
+        }
+        Board::from_diagram(&diagram).unwrap()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn every_stone_keeps_a_liberty((size, attempts) in games()) {
+            play_game(size, &attempts, |_, after, _| {
+                for loc in points(after) {
+                    if after.get(loc) != Color::Empty {
+                        assert!(after.count_liberties(loc) > 0, "no liberties at {}, {}", loc.row, loc.col);
+                    }
+                }
+            });
+        }
+
+        #[test]
+        fn captures_equal_the_stones_removed((size, attempts) in games()) {
+            play_game(size, &attempts, |before, after, mv| {
+                let opponent = mv.player.opponent();
+                let removed = count_stones(before, opponent) - count_stones(after, opponent);
+                let captured = after.get_captures(mv.player) - before.get_captures(mv.player);
+                assert_eq!(captured, removed as isize);
+                assert_eq!(after.get_captures(opponent), before.get_captures(opponent));
+            });
+        }
+
+        #[test]
+        fn undo_restores_the_board_before_the_move((size, attempts) in games()) {
+            play_game(size, &attempts, |before, after, _| {
+                assert!(after.clone().undo() == *before);
+            });
+        }
+
+        #[test]
+        fn stones_on_the_board_and_captured_add_up_to_the_moves((size, attempts) in games()) {
+            let board = play_game(size, &attempts, |_, _, _| {});
+            for player in [Player::Black, Player::White] {
+                let placed = board
+                    .get_game_history()
+                    .iter()
+                    .filter(|mv| mv.player == player && !mv.is_pass())
+                    .count();
+                let lost = board.get_captures(player.opponent()) as usize;
+                prop_assert_eq!(count_stones(&board, player) + lost, placed);
+            }
+        }
+
+        #[test]
+        fn flipping_the_colors_flips_the_score((size, attempts) in games()) {
+            let board = play_game(size, &attempts, |_, _, _| {});
+            let margin = board.final_score().black_margin().unwrap();
+            let flipped_margin = flipped(&board).final_score().black_margin().unwrap();
+            prop_assert_eq!(flipped_margin, -margin);
+        }
+    }
 }
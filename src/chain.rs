@@ -0,0 +1,318 @@
+// Chains of stones kept up to date on every placement and capture, so capture checks need no flood fill.
+// Lingo:
+//     chain           - a group of connected stones of one color, a circular list of its stones
+//     head            - the stone a chain is known by, every stone points at the head of its chain
+//     pseudo-liberty  - a pair of a stone and an empty neighbour, a liberty next to k stones is counted k times
+// A chain has no liberties exactly when it has no pseudo-liberties, which is all capture detection needs.
+
+use crate::board::{Color, Loc, PointSet};
+use std::collections::{HashMap, HashSet};
+
+// Head of points without a stone
+const NONE: usize = usize::MAX;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chains {
+    cols: usize,
+    // Indexed by point
+    head: Vec<usize>,
+    next: Vec<usize>,
+    // Indexed by head, stale for points that are not heads
+    size: Vec<usize>,
+    pseudo_liberties: Vec<usize>,
+}
+
+impl Chains {
+    // Sizes include sentinels, like in Board::new
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Chains {
+            cols,
+            head: vec![NONE; rows * cols],
+            next: vec![NONE; rows * cols],
+            size: vec![0; rows * cols],
+            pseudo_liberties: vec![0; rows * cols],
+        }
+    }
+
+    // Chains for a position that was set up stone by stone
+    pub fn from_fields(fields: &[Vec<Color>]) -> Self {
+        let mut chains = Chains::new(fields.len(), fields[0].len());
+        let mut placed: Vec<Vec<Color>> = fields
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|color| match color {
+                        Color::Black | Color::White => Color::Empty,
+                        color => *color,
+                    })
+                    .collect()
+            })
+            .collect();
+        for (row, colors) in fields.iter().enumerate() {
+            for (col, color) in colors.iter().enumerate() {
+                if matches!(color, Color::Black | Color::White) {
+                    placed[row][col] = *color;
+                    chains.add_stone(Loc { row, col }, &placed);
+                }
+            }
+        }
+        chains
+    }
+
+    fn index(&self, loc: Loc) -> usize {
+        loc.row * self.cols + loc.col
+    }
+
+    // Locations from outside, like Board::get, may point anywhere
+    fn contains(&self, loc: Loc) -> bool {
+        loc.col < self.cols && loc.row < self.head.len() / self.cols
+    }
+
+    fn loc(&self, index: usize) -> Loc {
+        Loc {
+            row: index / self.cols,
+            col: index % self.cols,
+        }
+    }
+
+    // The stone has to be on the fields already, the point must have been empty before
+    pub fn add_stone(&mut self, loc: Loc, fields: &[Vec<Color>]) {
+        let point = self.index(loc);
+        let color = fields[loc.row][loc.col];
+        self.head[point] = point;
+        self.next[point] = point;
+        self.size[point] = 1;
+        self.pseudo_liberties[point] = 0;
+
+        for nbr in loc.all_4nbr() {
+            let nbr_color = fields[nbr.row][nbr.col];
+            let nbr_head = self.head[self.index(nbr)];
+            if nbr_color == Color::Empty {
+                self.pseudo_liberties[self.head[point]] += 1;
+            } else if nbr_head != NONE {
+                // The new stone took a liberty of the neighbouring chain
+                self.pseudo_liberties[nbr_head] -= 1;
+                if nbr_color == color {
+                    self.merge(self.head[point], nbr_head);
+                }
+            }
+        }
+    }
+
+    // The smaller chain joins the bigger one
+    fn merge(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let (big, small) = if self.size[a] >= self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let mut stone = small;
+        loop {
+            self.head[stone] = big;
+            stone = self.next[stone];
+            if stone == small {
+                break;
+            }
+        }
+        self.next.swap(big, small);
+        self.size[big] += self.size[small];
+        self.pseudo_liberties[big] += self.pseudo_liberties[small];
+    }
+
+    // Takes the whole chain off and gives its points back as liberties to the neighbouring chains,
+    // the caller empties the returned points on the board
+    pub fn remove_chain(&mut self, loc: Loc) -> Vec<Loc> {
+        let stones = self.stones(loc);
+        for stone in &stones {
+            let point = self.index(*stone);
+            self.head[point] = NONE;
+            self.next[point] = NONE;
+        }
+        for stone in &stones {
+            for nbr in stone.all_4nbr() {
+                let nbr_head = self.head[self.index(nbr)];
+                if nbr_head != NONE {
+                    self.pseudo_liberties[nbr_head] += 1;
+                }
+            }
+        }
+        stones
+    }
+
+    pub fn has_stone(&self, loc: Loc) -> bool {
+        self.contains(loc) && self.head[self.index(loc)] != NONE
+    }
+
+    pub fn stones(&self, loc: Loc) -> Vec<Loc> {
+        if !self.has_stone(loc) {
+            return vec![];
+        }
+        let start = self.index(loc);
+        let mut stones = vec![];
+        let mut stone = start;
+        loop {
+            stones.push(self.loc(stone));
+            stone = self.next[stone];
+            if stone == start {
+                break;
+            }
+        }
+        stones
+    }
+
+    // For chains that come from outside, like a snapshot: the fields' stones in the same chains with the same
+    // pseudo-liberties, heads may differ. Checks every index it follows, so it can't panic or loop forever
+    pub fn agrees_with(&self, fields: &[Vec<Color>]) -> bool {
+        let expected = Chains::from_fields(fields);
+        let points = expected.head.len();
+        if self.cols != expected.cols
+            || [&self.head, &self.next, &self.size, &self.pseudo_liberties]
+                .iter()
+                .any(|list| list.len() != points)
+        {
+            return false;
+        }
+
+        // Expected heads to heads, one to one
+        let mut heads = HashMap::new();
+        for point in 0..points {
+            let (head, expected_head) = (self.head[point], expected.head[point]);
+            if (head == NONE) != (expected_head == NONE) {
+                return false;
+            }
+            if head == NONE {
+                continue;
+            }
+            if head >= points || self.head[head] != head {
+                return false;
+            }
+            if *heads.entry(expected_head).or_insert(head) != head {
+                return false;
+            }
+        }
+        if heads.values().collect::<HashSet<_>>().len() != heads.len() {
+            return false;
+        }
+
+        heads.iter().all(|(expected_head, head)| {
+            let size = expected.size[*expected_head];
+            if self.size[*head] != size
+                || self.pseudo_liberties[*head] != expected.pseudo_liberties[*expected_head]
+            {
+                return false;
+            }
+            // The circular list gets back to the head only after every stone of the chain
+            let mut point = *head;
+            for step in 1..=size {
+                point = self.next[point];
+                if point >= points
+                    || self.head[point] != *head
+                    || (point == *head) != (step == size)
+                {
+                    return false;
+                }
+            }
+            true
+        })
+    }
+
+    // O(1), the question capture detection asks after every move
+    pub fn has_liberties(&self, loc: Loc) -> bool {
+        self.has_stone(loc) && self.pseudo_liberties[self.head[self.index(loc)]] > 0
+    }
+
+    // Each liberty once, walks the stones of the chain
+    pub fn liberties(&self, loc: Loc, fields: &[Vec<Color>]) -> Vec<Loc> {
+        if !self.has_liberties(loc) {
+            return vec![];
+        }
+        let mut seen = PointSet::new((self.head.len() / self.cols, self.cols));
+        let mut liberties = vec![];
+        for stone in self.stones(loc) {
+            for nbr in stone.all_4nbr() {
+                if fields[nbr.row][nbr.col] == Color::Empty && seen.insert(nbr) {
+                    liberties.push(nbr);
+                }
+            }
+        }
+        liberties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, Move, Player};
+
+    fn play(board: &mut Board, player: Player, row: usize, col: usize) {
+        board.play(&Move::new(player, Loc { row, col }));
+    }
+
+    #[test]
+    fn chains_merge_and_share_their_liberties() {
+        let mut board = Board::from_diagram(". . . .|. . . .|. . . .").unwrap();
+        play(&mut board, Player::Black, 2, 1);
+        play(&mut board, Player::White, 1, 4);
+        play(&mut board, Player::Black, 2, 3);
+        assert_eq!(board.count_liberties(Loc { row: 2, col: 1 }), 3);
+        play(&mut board, Player::White, 3, 4);
+        // Connecting the two stones, 2, 2 stops being a liberty
+        play(&mut board, Player::Black, 2, 2);
+        assert_eq!(
+            board.group_stones(Loc { row: 2, col: 1 }),
+            [
+                Loc { row: 2, col: 1 },
+                Loc { row: 2, col: 2 },
+                Loc { row: 2, col: 3 }
+            ]
+        );
+        assert_eq!(board.count_liberties(Loc { row: 2, col: 3 }), 7);
+    }
+
+    #[test]
+    fn captured_chains_give_their_points_back_as_liberties() {
+        let mut board = Board::from_diagram(". O # .|O # . #|. O # .; Black to play").unwrap();
+        assert_eq!(board.count_liberties(Loc { row: 1, col: 3 }), 2);
+        play(&mut board, Player::Black, 2, 3);
+        assert!(board.get_captures(Player::Black) == 1);
+        assert_eq!(board.count_liberties(Loc { row: 2, col: 3 }), 1);
+        assert_eq!(board.count_liberties(Loc { row: 1, col: 3 }), 1);
+    }
+
+    #[test]
+    fn chains_set_up_from_a_position_match_chains_played_move_by_move() {
+        let mut played = Board::new(7, 7, 0.5);
+        let moves = [
+            (1, 1),
+            (2, 2),
+            (1, 2),
+            (2, 1),
+            (3, 3),
+            (1, 3),
+            (3, 1),
+            (4, 4),
+            (2, 3),
+            (5, 5),
+        ];
+        for (i, (row, col)) in moves.iter().enumerate() {
+            let player = if i % 2 == 0 {
+                Player::Black
+            } else {
+                Player::White
+            };
+            play(&mut played, player, *row, *col);
+        }
+        let set_up = Board::from_diagram(&played.to_diagram()).unwrap();
+        let (rows, cols) = played.board_size();
+        for loc in Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| loc.is_on_board((rows, cols)))
+        {
+            assert_eq!(played.group_stones(loc), set_up.group_stones(loc));
+            assert_eq!(played.count_liberties(loc), set_up.count_liberties(loc));
+        }
+    }
+}
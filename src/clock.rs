@@ -0,0 +1,541 @@
+// Game clocks, charged per turn from an injectable time source so tests don't have to sleep.
+// Lingo:
+//     main time - the first part of the time, used up before any overtime
+//     overtime  - byo-yomi periods or Canadian periods that follow the main time
+//     flag      - a player's time has run out, the game is lost on time
+
+use crate::board::Player;
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Rough guess of how many more moves a player makes, main time is spread over them
+const EXPECTED_MOVES_LEFT: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    Unlimited,
+    Absolute {
+        main: Duration,
+    },
+    // A period is used up only when a move takes longer than it
+    ByoYomi {
+        main: Duration,
+        period: Duration,
+        periods: usize,
+    },
+    // The given number of stones has to be played within each period
+    Canadian {
+        main: Duration,
+        period: Duration,
+        stones: usize,
+    },
+    // The increment is added after every move
+    Fischer {
+        main: Duration,
+        increment: Duration,
+    },
+}
+
+fn seconds(arg: &str) -> Option<Duration> {
+    arg.parse::<u64>().ok().map(Duration::from_secs)
+}
+
+impl TimeControl {
+    // Arguments of GTP time_settings: main time, byo-yomi time and stones, which is Canadian overtime
+    pub fn from_gtp(args: &[&str]) -> Option<Self> {
+        if args.len() != 3 {
+            return None;
+        }
+        let main = seconds(args[0])?;
+        let period = seconds(args[1])?;
+        let stones = args[2].parse::<usize>().ok()?;
+        Some(if period.is_zero() {
+            TimeControl::Absolute { main }
+        } else if stones == 0 {
+            TimeControl::Unlimited
+        } else {
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            }
+        })
+    }
+
+    // Arguments of kgs-time_settings, also used on the command line, fischer is our own addition
+    pub fn from_kgs(args: &[&str]) -> Option<Self> {
+        let number = |i: usize| args.get(i).and_then(|arg| arg.parse::<usize>().ok());
+        let time = |i: usize| args.get(i).and_then(|arg| seconds(arg));
+        let control = match *args.first()? {
+            "none" => TimeControl::Unlimited,
+            "absolute" => TimeControl::Absolute { main: time(1)? },
+            "byoyomi" => TimeControl::ByoYomi {
+                main: time(1)?,
+                period: time(2)?,
+                periods: number(3)?,
+            },
+            "canadian" => TimeControl::Canadian {
+                main: time(1)?,
+                period: time(2)?,
+                stones: number(3)?,
+            },
+            "fischer" => TimeControl::Fischer {
+                main: time(1)?,
+                increment: time(2)?,
+            },
+            _ => return None,
+        };
+        Some(control)
+    }
+
+    fn main_time(&self) -> Duration {
+        match self {
+            TimeControl::Unlimited => Duration::MAX,
+            TimeControl::Absolute { main }
+            | TimeControl::ByoYomi { main, .. }
+            | TimeControl::Canadian { main, .. }
+            | TimeControl::Fischer { main, .. } => *main,
+        }
+    }
+}
+
+pub trait TimeSource {
+    // Only differences between two calls matter
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemTime {
+    start: Instant,
+}
+
+impl SystemTime {
+    pub fn new() -> Self {
+        SystemTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemTime {
+    fn default() -> Self {
+        SystemTime::new()
+    }
+}
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Moves only when told to, clones share the same time so a test can keep one and give one to the clock
+#[derive(Debug, Clone, Default)]
+pub struct MockTime {
+    now: Rc<Cell<Duration>>,
+}
+
+impl MockTime {
+    pub fn new() -> Self {
+        MockTime::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl TimeSource for MockTime {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PlayerClock {
+    main: Duration,
+    // Left in the current overtime period
+    period: Duration,
+    // Byo-yomi periods left
+    periods: usize,
+    // Stones left to play in the current Canadian period, 0 before overtime starts
+    stones: usize,
+    flagged: bool,
+}
+
+impl PlayerClock {
+    fn new(control: TimeControl) -> Self {
+        let (period, periods) = match control {
+            TimeControl::ByoYomi {
+                period, periods, ..
+            } => (period, periods),
+            TimeControl::Canadian { period, .. } => (period, 0),
+            _ => (Duration::ZERO, 0),
+        };
+        PlayerClock {
+            main: control.main_time(),
+            period,
+            periods,
+            stones: 0,
+            flagged: false,
+        }
+    }
+}
+
+pub struct Clock {
+    control: TimeControl,
+    source: Box<dyn TimeSource>,
+    black: PlayerClock,
+    white: PlayerClock,
+    // The player whose clock runs and when it was started
+    running: Option<(Player, Duration)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, source: Box<dyn TimeSource>) -> Self {
+        let player_clock = PlayerClock::new(control);
+        Clock {
+            control,
+            source,
+            black: player_clock,
+            white: player_clock,
+            running: None,
+        }
+    }
+
+    pub fn get_control(&self) -> TimeControl {
+        self.control
+    }
+
+    // Like GTP time_settings, both clocks start over and none of them runs
+    pub fn set_control(&mut self, control: TimeControl) {
+        self.control = control;
+        self.black = PlayerClock::new(control);
+        self.white = PlayerClock::new(control);
+        self.running = None;
+    }
+
+    fn get_player_clock(&self, player: Player) -> &PlayerClock {
+        match player {
+            Player::Black => &self.black,
+            Player::White => &self.white,
+        }
+    }
+
+    fn get_player_clock_mut(&mut self, player: Player) -> &mut PlayerClock {
+        match player {
+            Player::Black => &mut self.black,
+            Player::White => &mut self.white,
+        }
+    }
+
+    // Stops the other player's clock first, false when their flag fell like stop
+    pub fn start(&mut self, player: Player) -> bool {
+        let in_time = self.stop();
+        self.running = Some((player, self.source.now()));
+        in_time
+    }
+
+    // Charges the turn to the player whose clock was running, false when their flag fell
+    pub fn stop(&mut self) -> bool {
+        let (player, started) = match self.running.take() {
+            Some(running) => running,
+            None => return true,
+        };
+        let elapsed = self.source.now().saturating_sub(started);
+        let control = self.control;
+        let clock = self.get_player_clock_mut(player);
+        charge(clock, control, elapsed);
+        !clock.flagged
+    }
+
+    pub fn has_flagged(&self, player: Player) -> bool {
+        self.get_player_clock(player).flagged
+    }
+
+    // Like GTP time_left, stones is 0 in main time and counts stones or byo-yomi periods in overtime
+    // The controller's clock is the one that counts, a flag that fell here is raised again
+    pub fn set_time_left(&mut self, player: Player, time: Duration, stones: usize) {
+        let control = self.control;
+        let clock = self.get_player_clock_mut(player);
+        clock.flagged = false;
+        if stones == 0 {
+            clock.main = time;
+            return;
+        }
+        clock.main = Duration::ZERO;
+        match control {
+            TimeControl::ByoYomi { period, .. } => {
+                clock.period = period.min(time);
+                clock.periods = stones;
+            }
+            TimeControl::Canadian { .. } => {
+                clock.period = time;
+                clock.stones = stones;
+            }
+            _ => clock.main = time,
+        }
+    }
+
+    // How long the player can think about the next move without getting into trouble
+    pub fn move_budget(&self, player: Player) -> Duration {
+        let clock = self.get_player_clock(player);
+        let share = clock.main / EXPECTED_MOVES_LEFT;
+        match self.control {
+            TimeControl::Unlimited => Duration::MAX,
+            TimeControl::Absolute { .. } => share,
+            TimeControl::ByoYomi { .. } => share.saturating_add(clock.period),
+            TimeControl::Canadian { stones, .. } => {
+                let stones_left = if clock.stones == 0 {
+                    stones
+                } else {
+                    clock.stones
+                };
+                let stones_left = stones_left.clamp(1, u32::MAX as usize) as u32;
+                share.saturating_add(clock.period / stones_left)
+            }
+            TimeControl::Fischer { increment, .. } => share.saturating_add(increment),
+        }
+    }
+
+    fn describe(&self, player: Player) -> String {
+        let clock = self.get_player_clock(player);
+        let time = if clock.flagged {
+            String::from("flag fell")
+        } else if !clock.main.is_zero() || self.control == TimeControl::Unlimited {
+            format_duration(clock.main)
+        } else {
+            match self.control {
+                TimeControl::ByoYomi { .. } => {
+                    format!("{} x {}", clock.periods, format_duration(clock.period))
+                }
+                TimeControl::Canadian { stones, .. } => {
+                    let stones_left = if clock.stones == 0 {
+                        stones
+                    } else {
+                        clock.stones
+                    };
+                    format!(
+                        "{} for {} stone(s)",
+                        format_duration(clock.period),
+                        stones_left
+                    )
+                }
+                _ => format_duration(clock.main),
+            }
+        };
+        format!("{:?} {}", player, time)
+    }
+}
+
+fn charge(clock: &mut PlayerClock, control: TimeControl, elapsed: Duration) {
+    if elapsed <= clock.main {
+        clock.main -= elapsed;
+        if let TimeControl::Fischer { increment, .. } = control {
+            clock.main = clock.main.saturating_add(increment);
+        }
+        return;
+    }
+    let overtime = elapsed - clock.main;
+    clock.main = Duration::ZERO;
+
+    match control {
+        TimeControl::ByoYomi { period, .. } => {
+            // Every period the move runs over is lost, starting with what is left of the current one
+            // as set by time_left, the next one starts full
+            if overtime > clock.period {
+                let beyond = overtime - clock.period;
+                let lost = match (beyond.as_nanos() - 1).checked_div(period.as_nanos()) {
+                    Some(lost) => usize::try_from(lost)
+                        .unwrap_or(usize::MAX)
+                        .saturating_add(1),
+                    None => clock.periods,
+                };
+                clock.periods = clock.periods.saturating_sub(lost);
+                clock.flagged = clock.periods == 0;
+            }
+            clock.period = period;
+        }
+        TimeControl::Canadian { period, stones, .. } => {
+            // A period of 0 stones would never end, it counts as 1 stone
+            if clock.stones == 0 {
+                clock.period = period;
+                clock.stones = stones.max(1);
+            }
+            if overtime > clock.period {
+                clock.flagged = true;
+                return;
+            }
+            clock.period -= overtime;
+            clock.stones -= 1;
+            if clock.stones == 0 {
+                clock.period = period;
+                clock.stones = stones.max(1);
+            }
+        }
+        _ => clock.flagged = true,
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration == Duration::MAX {
+        return String::from("no limit");
+    }
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} | {}",
+            self.describe(Player::Black),
+            self.describe(Player::White)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Player;
+    use crate::clock::{Clock, MockTime, TimeControl};
+    use std::time::Duration;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn turn(clock: &mut Clock, time: &MockTime, player: Player, took: u64) -> bool {
+        clock.start(player);
+        time.advance(secs(took));
+        clock.stop()
+    }
+
+    #[test]
+    fn absolute_time_runs_out_and_fischer_adds_increments() {
+        let time = MockTime::new();
+        let mut clock = Clock::new(
+            TimeControl::Absolute { main: secs(60) },
+            Box::new(time.clone()),
+        );
+        assert!(turn(&mut clock, &time, Player::Black, 59));
+        assert!(!turn(&mut clock, &time, Player::Black, 2));
+        assert!(clock.has_flagged(Player::Black));
+        assert!(!clock.has_flagged(Player::White));
+
+        let control = TimeControl::Fischer {
+            main: secs(60),
+            increment: secs(10),
+        };
+        let mut clock = Clock::new(control, Box::new(time.clone()));
+        for _ in 0..5 {
+            assert!(turn(&mut clock, &time, Player::White, 15));
+        }
+        assert_eq!(clock.to_string(), "Black 1:00 | White 0:35");
+    }
+
+    #[test]
+    fn byo_yomi_periods_are_lost_only_when_a_move_runs_over() {
+        let time = MockTime::new();
+        let control = TimeControl::ByoYomi {
+            main: secs(10),
+            period: secs(30),
+            periods: 3,
+        };
+        let mut clock = Clock::new(control, Box::new(time.clone()));
+        assert!(turn(&mut clock, &time, Player::Black, 40));
+        assert!(turn(&mut clock, &time, Player::Black, 25));
+        assert_eq!(clock.to_string(), "Black 3 x 0:30 | White 0:10");
+        assert!(turn(&mut clock, &time, Player::Black, 61));
+        assert_eq!(clock.to_string(), "Black 1 x 0:30 | White 0:10");
+        assert!(!turn(&mut clock, &time, Player::Black, 31));
+        assert_eq!(clock.to_string(), "Black flag fell | White 0:10");
+    }
+
+    #[test]
+    fn a_byo_yomi_period_shortened_by_time_left_is_enforced() {
+        let time = MockTime::new();
+        let control = TimeControl::ByoYomi {
+            main: secs(0),
+            period: secs(30),
+            periods: 2,
+        };
+        let mut clock = Clock::new(control, Box::new(time.clone()));
+        clock.set_time_left(Player::Black, secs(20), 2);
+        assert!(turn(&mut clock, &time, Player::Black, 25));
+        assert_eq!(clock.to_string(), "Black 1 x 0:30 | White 2 x 0:30");
+        clock.set_time_left(Player::Black, secs(10), 1);
+        assert!(!turn(&mut clock, &time, Player::Black, 15));
+    }
+
+    #[test]
+    fn canadian_periods_restart_after_enough_stones() {
+        let time = MockTime::new();
+        let control = TimeControl::Canadian {
+            main: secs(0),
+            period: secs(60),
+            stones: 3,
+        };
+        let mut clock = Clock::new(control, Box::new(time.clone()));
+        assert!(turn(&mut clock, &time, Player::White, 20));
+        assert!(turn(&mut clock, &time, Player::White, 20));
+        assert_eq!(clock.move_budget(Player::White), secs(20));
+        assert!(turn(&mut clock, &time, Player::White, 20));
+        assert_eq!(
+            clock.to_string(),
+            "Black 1:00 for 3 stone(s) | White 1:00 for 3 stone(s)"
+        );
+        assert!(turn(&mut clock, &time, Player::White, 50));
+        assert!(!turn(&mut clock, &time, Player::White, 11));
+    }
+
+    #[test]
+    fn gtp_settings_and_time_left_are_understood() {
+        assert_eq!(
+            TimeControl::from_gtp(&["300", "30", "5"]),
+            Some(TimeControl::Canadian {
+                main: secs(300),
+                period: secs(30),
+                stones: 5
+            })
+        );
+        assert_eq!(
+            TimeControl::from_gtp(&["300", "0", "0"]),
+            Some(TimeControl::Absolute { main: secs(300) })
+        );
+        assert_eq!(
+            TimeControl::from_gtp(&["0", "1", "0"]),
+            Some(TimeControl::Unlimited)
+        );
+        assert_eq!(TimeControl::from_kgs(&["byoyomi", "300"]), None);
+
+        let control = TimeControl::from_kgs(&["byoyomi", "300", "30", "5"]).unwrap();
+        let mut clock = Clock::new(control, Box::new(MockTime::new()));
+        clock.set_time_left(Player::Black, secs(20), 2);
+        assert_eq!(clock.to_string(), "Black 2 x 0:20 | White 5:00");
+    }
+
+    #[test]
+    fn extreme_settings_neither_overflow_nor_hang() {
+        let time = MockTime::new();
+        for args in [
+            "byoyomi 0 0 18446744073709551615",
+            "canadian 0 10 0",
+            "canadian 0 1 18446744073709551615",
+            "fischer 18446744073709551615 18446744073709551615",
+        ] {
+            let args: Vec<&str> = args.split_whitespace().collect();
+            let mut clock = Clock::new(
+                TimeControl::from_kgs(&args).unwrap(),
+                Box::new(time.clone()),
+            );
+            for player in [Player::Black, Player::White] {
+                turn(&mut clock, &time, player, 5);
+                clock.move_budget(player);
+            }
+            clock.to_string();
+        }
+    }
+}
@@ -0,0 +1,383 @@
+// The Go Text Protocol for the standard game, so GUIs and go server clients can play against the policy.
+// Lingo:
+//     controller - the program that sends the commands, a GUI or a go server client
+//     vertex     - a point as GTP writes it, a column letter without I and a row counted from the bottom, e.g. D4
+//     response   - "=" and the answer, or "?" and an error, with the command's id if it had one and an empty line after
+// The clock follows time_settings, kgs-time_settings and time_left and runs while a move is generated.
+
+use crate::board::{Board, Loc, Move, MoveKind, Player};
+use crate::clock::{Clock, TimeControl, TimeSource};
+use crate::policy::Policy;
+use crate::seed::{new_rng, GameRng};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+const DEFAULT_SIZE: usize = 19;
+const DEFAULT_KOMI: f32 = 7.5;
+
+const COMMANDS: [&str; 17] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "undo",
+    "showboard",
+    "final_score",
+    "time_settings",
+    "kgs-time_settings",
+    "time_left",
+];
+
+fn parse_color(arg: Option<&&str>) -> Result<Player, String> {
+    match arg.map(|arg| arg.to_ascii_lowercase()).as_deref() {
+        Some("b") | Some("black") => Ok(Player::Black),
+        Some("w") | Some("white") => Ok(Player::White),
+        _ => Err(String::from("invalid color")),
+    }
+}
+
+// Sizes include sentinels, only passes are vertices besides points
+pub fn parse_vertex(text: &str, board_size: (usize, usize)) -> Option<MoveKind> {
+    if text.eq_ignore_ascii_case("pass") {
+        return Some(MoveKind::Pass);
+    }
+    let mut chars = text.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let col = COLUMNS.find(letter)? + 1;
+    let number = chars.as_str().parse::<usize>().ok()?;
+    let row = (board_size.0 - 1).checked_sub(number)?;
+    let loc = Loc { row, col };
+    loc.is_on_board(board_size).then_some(MoveKind::Play(loc))
+}
+
+pub fn format_vertex(kind: MoveKind, board_size: (usize, usize)) -> String {
+    match kind {
+        MoveKind::Play(loc) => {
+            let letter = COLUMNS.chars().nth(loc.col - 1).unwrap_or('?');
+            format!("{}{}", letter, board_size.0 - 1 - loc.row)
+        }
+        MoveKind::Pass => String::from("pass"),
+        MoveKind::Resign => String::from("resign"),
+    }
+}
+
+pub struct Engine {
+    board: Board,
+    komi: f32,
+    clock: Clock,
+    policy: Policy,
+    rng: GameRng,
+    quit: bool,
+}
+
+impl Engine {
+    pub fn new(source: Box<dyn TimeSource>, seed: u64) -> Self {
+        Engine {
+            board: Board::new(DEFAULT_SIZE + 2, DEFAULT_SIZE + 2, DEFAULT_KOMI),
+            komi: DEFAULT_KOMI,
+            clock: Clock::new(TimeControl::Unlimited, source),
+            policy: Policy::default(),
+            rng: new_rng(seed),
+            quit: false,
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    // The whole response to one line, None for empty lines and comments
+    pub fn respond(&mut self, line: &str) -> Option<String> {
+        let line: String = line
+            .split('#')
+            .next()
+            .unwrap_or("")
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        let mut words = line.split_whitespace().peekable();
+        let id = match words.peek()?.parse::<u64>() {
+            Ok(id) => {
+                words.next();
+                id.to_string()
+            }
+            Err(_) => String::new(),
+        };
+        let command = words.next().unwrap_or("");
+        let args: Vec<&str> = words.collect();
+        let response = match self.execute(command, &args) {
+            Ok(answer) => format!("={} {}", id, answer),
+            Err(error) => format!("?{} {}", id, error),
+        };
+        Some(response.trim_end().to_string() + "\n\n")
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match command {
+            "protocol_version" => Ok(String::from("2")),
+            "name" => Ok(String::from("go_board")),
+            "version" => Ok(String::from(env!("CARGO_PKG_VERSION"))),
+            "known_command" => {
+                let known = args.first().is_some_and(|name| COMMANDS.contains(name));
+                Ok(known.to_string())
+            }
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => {
+                self.quit = true;
+                Ok(String::new())
+            }
+            "boardsize" => {
+                let size = args.first().and_then(|arg| arg.parse::<usize>().ok());
+                match size {
+                    Some(size) if (1..=COLUMNS.len()).contains(&size) => {
+                        self.board = Board::new(size + 2, size + 2, self.komi);
+                        Ok(String::new())
+                    }
+                    Some(_) => Err(String::from("unacceptable size")),
+                    None => Err(String::from("syntax error")),
+                }
+            }
+            "clear_board" => {
+                let (rows, cols) = self.board.board_size();
+                self.board = Board::new(rows, cols, self.komi);
+                Ok(String::new())
+            }
+            "komi" => {
+                self.komi = args
+                    .first()
+                    .and_then(|arg| arg.parse::<f32>().ok())
+                    .filter(|komi| komi.is_finite())
+                    .ok_or("syntax error")?;
+                // The moves so far are played again on a board with the new komi
+                let (rows, cols) = self.board.board_size();
+                let moves = self.board.get_game_history().clone();
+                self.board = Board::new(rows, cols, self.komi);
+                for mv in &moves {
+                    self.board.play(mv);
+                }
+                Ok(String::new())
+            }
+            "play" => self.play(args),
+            "genmove" => self.genmove(args),
+            "undo" => {
+                if self.board.get_game_history().is_empty() {
+                    return Err(String::from("cannot undo"));
+                }
+                self.board = self.board.clone().undo();
+                Ok(String::new())
+            }
+            "showboard" => Ok(format!("\n{}", self.board.to_diagram().trim_end())),
+            "final_score" => Ok(self.board.final_score().to_sgf()),
+            "time_settings" => {
+                let control = TimeControl::from_gtp(args).ok_or("syntax error")?;
+                self.clock.set_control(control);
+                Ok(String::new())
+            }
+            "kgs-time_settings" => {
+                let control = TimeControl::from_kgs(args).ok_or("syntax error")?;
+                self.clock.set_control(control);
+                Ok(String::new())
+            }
+            "time_left" => {
+                let player = parse_color(args.first())?;
+                let time = args.get(1).and_then(|arg| arg.parse::<u64>().ok());
+                let stones = args.get(2).and_then(|arg| arg.parse::<usize>().ok());
+                match (time, stones) {
+                    (Some(time), Some(stones)) => {
+                        self.clock
+                            .set_time_left(player, Duration::from_secs(time), stones);
+                        Ok(String::new())
+                    }
+                    _ => Err(String::from("syntax error")),
+                }
+            }
+            _ => Err(String::from("unknown command")),
+        }
+    }
+
+    // Either color may play, controllers send handicap stones and setups as moves in a row
+    fn play(&mut self, args: &[&str]) -> Result<String, String> {
+        let player = parse_color(args.first())?;
+        let vertex = args.get(1).ok_or("syntax error")?;
+        let kind = parse_vertex(vertex, self.board.board_size()).ok_or("invalid coordinate")?;
+        let mv = Move { player, kind };
+        let waiting = self.board.get_current_player();
+        self.board.set_current_player(player);
+        if !self.board.move_is_valid(&mv) {
+            self.board.set_current_player(waiting);
+            return Err(String::from("illegal move"));
+        }
+        self.board.play(&mv);
+        Ok(String::new())
+    }
+
+    // A move that comes after the flag fell would lose on time anyway, the engine resigns instead
+    fn genmove(&mut self, args: &[&str]) -> Result<String, String> {
+        let player = parse_color(args.first())?;
+        self.board.set_current_player(player);
+        self.clock.start(player);
+        let kind = self.policy.gen_move(&self.board, &mut self.rng);
+        let kind = if self.clock.stop() {
+            kind
+        } else {
+            MoveKind::Resign
+        };
+        self.board.play(&Move { player, kind });
+        Ok(format_vertex(kind, self.board.board_size()))
+    }
+}
+
+// Answers commands until quit or the end of the input
+pub fn run(engine: &mut Engine, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        if let Some(response) = engine.respond(&line?) {
+            output.write_all(response.as_bytes())?;
+            output.flush()?;
+        }
+        if engine.has_quit() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Loc, MoveKind, Player};
+    use crate::clock::{MockTime, TimeControl, TimeSource};
+    use crate::gtp::{format_vertex, parse_vertex, run, Engine};
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    fn new_engine() -> Engine {
+        Engine::new(Box::new(MockTime::new()), 3)
+    }
+
+    #[test]
+    fn vertices_count_rows_from_the_bottom_and_skip_i() {
+        let size = (21, 21);
+        let d4 = MoveKind::Play(Loc { row: 16, col: 4 });
+        assert_eq!(parse_vertex("D4", size), Some(d4));
+        assert_eq!(parse_vertex("d4", size), Some(d4));
+        assert_eq!(format_vertex(d4, size), "D4");
+        let j19 = MoveKind::Play(Loc { row: 1, col: 9 });
+        assert_eq!(parse_vertex("J19", size), Some(j19));
+        assert_eq!(format_vertex(j19, size), "J19");
+        assert_eq!(parse_vertex("PASS", size), Some(MoveKind::Pass));
+        for vertex in ["I5", "A20", "A0", "U1", "4D", "", "resign"] {
+            assert_eq!(parse_vertex(vertex, size), None);
+        }
+    }
+
+    #[test]
+    fn a_game_is_played_through_commands() {
+        let mut engine = new_engine();
+        assert_eq!(engine.respond("1 boardsize 9").unwrap(), "=1\n\n");
+        assert_eq!(engine.respond("  # a comment"), None);
+        assert_eq!(engine.respond("komi 0.5").unwrap(), "=\n\n");
+        assert_eq!(engine.respond("play black E5").unwrap(), "=\n\n");
+        assert_eq!(
+            engine.respond("2 play w e5").unwrap(),
+            "?2 illegal move\n\n"
+        );
+        assert_eq!(
+            engine.respond("play w Z5").unwrap(),
+            "? invalid coordinate\n\n"
+        );
+        let generated = engine.respond("genmove w").unwrap();
+        assert!(generated.starts_with("= ") && generated.ends_with("\n\n"));
+        assert_eq!(engine.get_board().get_game_history().len(), 2);
+        assert_eq!(engine.respond("undo").unwrap(), "=\n\n");
+        assert_eq!(engine.get_board().get_game_history().len(), 1);
+        assert_eq!(engine.respond("final_score").unwrap(), "= B+79.5\n\n");
+        assert_eq!(
+            engine.respond("known_command time_left").unwrap(),
+            "= true\n\n"
+        );
+        assert_eq!(
+            engine.respond("boardsize 30").unwrap(),
+            "? unacceptable size\n\n"
+        );
+        assert_eq!(engine.respond("fly").unwrap(), "? unknown command\n\n");
+    }
+
+    #[test]
+    fn time_commands_set_the_clock() {
+        let mut engine = new_engine();
+        engine.respond("time_settings 300 30 5");
+        assert_eq!(
+            engine.get_clock().get_control(),
+            TimeControl::Canadian {
+                main: Duration::from_secs(300),
+                period: Duration::from_secs(30),
+                stones: 5,
+            }
+        );
+        engine.respond("kgs-time_settings byoyomi 600 30 5");
+        engine.respond("time_left b 120 0");
+        engine.respond("time_left white 20 3");
+        assert_eq!(
+            engine.get_clock().to_string(),
+            "Black 2:00 | White 3 x 0:20"
+        );
+        assert_eq!(
+            engine.respond("time_left b 10").unwrap(),
+            "? syntax error\n\n"
+        );
+        assert_eq!(
+            engine.respond("kgs-time_settings hourglass 60").unwrap(),
+            "? syntax error\n\n"
+        );
+    }
+
+    // Every look at the time finds another second gone
+    #[derive(Default)]
+    struct Ticking(Cell<Duration>);
+
+    impl TimeSource for Ticking {
+        fn now(&self) -> Duration {
+            self.0.set(self.0.get() + Duration::from_secs(1));
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn the_engine_resigns_when_its_flag_falls_during_genmove() {
+        let mut engine = Engine::new(Box::new(Ticking::default()), 3);
+        engine.respond("boardsize 9");
+        engine.respond("kgs-time_settings absolute 60");
+        engine.respond("time_left b 0 0");
+        assert_eq!(engine.respond("genmove b").unwrap(), "= resign\n\n");
+        assert!(engine.get_board().resigned() == Some(Player::Black));
+
+        engine.respond("undo");
+        engine.respond("time_left b 60 0");
+        assert_ne!(engine.respond("genmove b").unwrap(), "= resign\n\n");
+        assert!(engine.get_board().resigned().is_none());
+    }
+
+    #[test]
+    fn the_loop_stops_at_quit() {
+        let mut engine = new_engine();
+        let mut output = vec![];
+        let input = "protocol_version\nquit\nname\n";
+        run(&mut engine, input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "= 2\n\n=\n\n");
+    }
+}
@@ -0,0 +1,284 @@
+// What a phantom player provably knows about the opponent, built only from referee announcements.
+// Lingo:
+//     opponent move  - a stone the opponent played, passes don't count
+//     stale          - a point proven empty before the opponent's latest moves, it may hold a stone now
+//     possible       - a point that may hold an opponent stone
+
+use crate::board::{Color, Loc, Player};
+use crate::referee::{Announcement, Event, Probe};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Known {
+    Own,
+    // Confirmed by a rejected attempt or inferred from a capture of own stones
+    Opponent,
+    // Proven empty when the opponent had played the given number of stones, unknown once they play more
+    EmptySince(usize),
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KnowledgeModel {
+    player: Player,
+    fields: Vec<Vec<Known>>,
+    opponent_moves: usize,
+    opponent_captured: usize,
+    // Penalty points each player gave away, own first
+    penalties: (f32, f32),
+}
+
+impl KnowledgeModel {
+    // Sizes include sentinels, like in Board::new
+    pub fn new(player: Player, rows: usize, cols: usize) -> Self {
+        let mut fields = vec![vec![Known::Invalid; cols]; rows];
+        for loc in Loc::get_all_on_board(rows, cols) {
+            if loc.is_on_board((rows, cols)) {
+                // The game starts on an empty board
+                fields[loc.row][loc.col] = Known::EmptySince(0);
+            }
+        }
+        KnowledgeModel {
+            player,
+            fields,
+            opponent_moves: 0,
+            opponent_captured: 0,
+            penalties: (0.0, 0.0),
+        }
+    }
+
+    // For models from a snapshot: the player and the board size, and no more captured opponent stones than moves
+    pub fn fits(&self, player: Player, (rows, cols): (usize, usize)) -> bool {
+        self.player == player
+            && self.fields.len() == rows
+            && self.fields.iter().all(|row| row.len() == cols)
+            && self.opponent_captured <= self.opponent_moves
+    }
+
+    pub fn get(&self, loc: Loc) -> Known {
+        self.fields[loc.row][loc.col]
+    }
+
+    fn set(&mut self, loc: Loc, known: Known) {
+        self.fields[loc.row][loc.col] = known;
+    }
+
+    pub fn get_player(&self) -> Player {
+        self.player
+    }
+
+    pub fn board_size(&self) -> (usize, usize) {
+        (self.fields.len(), self.fields[0].len())
+    }
+
+    pub fn all_points(&self) -> Vec<Loc> {
+        let (rows, cols) = self.board_size();
+        Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| loc.is_on_board((rows, cols)))
+            .collect()
+    }
+
+    // Exact, every opponent stone was either played or announced as captured
+    pub fn opponent_stone_count(&self) -> usize {
+        self.opponent_moves - self.opponent_captured
+    }
+
+    // Black's lead from the penalties so far, the part of an area count the board doesn't show
+    pub fn black_penalty_margin(&self) -> f32 {
+        let own = self.penalties.1 - self.penalties.0;
+        match self.player {
+            Player::Black => own,
+            Player::White => -own,
+        }
+    }
+
+    pub fn is_empty_now(&self, loc: Loc) -> bool {
+        self.get(loc) == Known::EmptySince(self.opponent_moves)
+    }
+
+    pub fn can_be_opponent(&self, loc: Loc) -> bool {
+        match self.get(loc) {
+            Known::Opponent => true,
+            Known::EmptySince(moves) => moves < self.opponent_moves,
+            Known::Own | Known::Invalid => false,
+        }
+    }
+
+    // Lower and upper bound on the number of opponent stones in the region
+    pub fn opponent_stones_in(&self, region: &[Loc]) -> (usize, usize) {
+        let count = |locs: &[Loc], known: bool| {
+            locs.iter()
+                .filter(|loc| {
+                    let confirmed = self.get(**loc) == Known::Opponent;
+                    if known {
+                        confirmed
+                    } else {
+                        !confirmed && self.can_be_opponent(**loc)
+                    }
+                })
+                .count()
+        };
+        let outside: Vec<Loc> = self
+            .all_points()
+            .into_iter()
+            .filter(|loc| !region.contains(loc))
+            .collect();
+
+        let total = self.opponent_stone_count();
+        let confirmed_inside = count(region, true);
+        let confirmed_outside = count(&outside, true);
+        let possible_inside = count(region, false);
+        let possible_outside = count(&outside, false);
+
+        let lower =
+            confirmed_inside.max(total.saturating_sub(confirmed_outside + possible_outside));
+        let upper = (confirmed_inside + possible_inside).min(total - confirmed_outside);
+        (lower, upper)
+    }
+
+    pub fn update(&mut self, announcement: &Announcement) {
+        if !announcement.is_for(self.player) {
+            return;
+        }
+        let now = Known::EmptySince(self.opponent_moves);
+        match &announcement.event {
+            Event::Played(loc) => self.set(*loc, Known::Own),
+            Event::Moved => self.opponent_moves += 1,
+            Event::Passed | Event::Resigned => (),
+            Event::Occupied(loc) => self.set(*loc, Known::Opponent),
+            Event::Answered(Probe::Occupied(loc), occupied) => {
+                if loc.is_on_board(self.board_size()) && self.get(*loc) != Known::Own {
+                    self.set(*loc, if *occupied { Known::Opponent } else { now });
+                }
+            }
+            // The opponent may have tried an own stone, so nothing follows about the point
+            Event::Answered(Probe::AnyCapture, _) | Event::Rejected(_) | Event::Forfeited => (),
+            Event::Penalized(points) => {
+                if announcement.mover == self.player {
+                    self.penalties.0 += points;
+                } else {
+                    self.penalties.1 += points;
+                }
+            }
+            // Not occupied by the opponent, so an empty point where the move was suicide or ko
+            Event::Illegal(loc) => {
+                if loc.is_on_board(self.board_size()) && self.get(*loc) != Known::Own {
+                    self.set(*loc, now);
+                }
+            }
+            Event::Captured(owner, stones) => {
+                if *owner == self.player {
+                    // A captured group has no liberties, so every point around it is an opponent stone
+                    for stone in stones {
+                        for nbr in stone.all_4nbr() {
+                            if self.get(nbr) != Known::Invalid && !stones.contains(&nbr) {
+                                self.set(nbr, Known::Opponent);
+                            }
+                        }
+                    }
+                } else {
+                    self.opponent_captured += stones.len();
+                }
+                for stone in stones {
+                    self.set(*stone, now);
+                }
+            }
+        }
+    }
+}
+
+// O and # are stones, . is empty for sure, ? may hold an opponent stone
+impl fmt::Display for KnowledgeModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (rows, cols) = self.board_size();
+        for row in 0..rows {
+            for col in 0..cols {
+                let loc = Loc { row, col };
+                match self.get(loc) {
+                    Known::Own => write!(f, "{} ", self.player.to_color())?,
+                    Known::Opponent => write!(f, "{} ", self.player.opponent().to_color())?,
+                    Known::Invalid => write!(f, "{} ", Color::Invalid)?,
+                    _ if self.is_empty_now(loc) => write!(f, "{} ", Color::Empty)?,
+                    _ => write!(f, "? ")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Loc, Player};
+    use crate::knowledge::Known;
+    use crate::referee::{Announcement, Event, Referee};
+
+    fn play(referee: &mut Referee, locs: &[(usize, usize)]) {
+        for (row, col) in locs {
+            referee.attempt(Loc {
+                row: *row,
+                col: *col,
+            });
+        }
+    }
+
+    #[test]
+    fn rejected_attempts_confirm_opponent_stones_and_opponent_moves_make_empty_points_stale() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        play(&mut referee, &[(3, 3), (3, 3), (2, 2)]);
+
+        let white = referee.get_view(Player::White).get_knowledge();
+        assert_eq!(white.get(Loc { row: 3, col: 3 }), Known::Opponent);
+        assert_eq!(white.get(Loc { row: 2, col: 2 }), Known::Own);
+        assert_eq!(white.opponent_stone_count(), 1);
+
+        let black = referee.get_view(Player::Black).get_knowledge();
+        assert!(black.can_be_opponent(Loc { row: 1, col: 1 }));
+        assert!(!black.is_empty_now(Loc { row: 1, col: 1 }));
+        assert_eq!(black.opponent_stone_count(), 1);
+    }
+
+    #[test]
+    fn capture_of_own_stones_reveals_the_surrounding_opponent_stones() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        play(&mut referee, &[(1, 1), (1, 2), (5, 5), (2, 1)]);
+
+        let black = referee.get_view(Player::Black).get_knowledge();
+        assert_eq!(black.get(Loc { row: 1, col: 2 }), Known::Opponent);
+        assert_eq!(black.get(Loc { row: 2, col: 1 }), Known::Opponent);
+        assert!(black.is_empty_now(Loc { row: 1, col: 1 }));
+        assert_eq!(black.opponent_stones_in(&[Loc { row: 1, col: 2 }]), (1, 1));
+
+        let white = referee.get_view(Player::White).get_knowledge();
+        assert!(white.is_empty_now(Loc { row: 1, col: 1 }));
+        assert_eq!(white.opponent_stone_count(), 1);
+
+        // Penalties are announced to both players
+        assert_eq!(black.black_penalty_margin(), 0.0);
+        let mut white = white.clone();
+        white.update(&Announcement {
+            mover: Player::Black,
+            event: Event::Penalized(2.0),
+        });
+        assert_eq!(white.black_penalty_margin(), -2.0);
+    }
+
+    #[test]
+    fn region_bounds_follow_from_the_exact_stone_count() {
+        let mut referee = Referee::new(5, 5, 0.5);
+        play(&mut referee, &[(1, 1), (2, 2)]);
+
+        let white = referee.get_view(Player::White).get_knowledge();
+        let everything_but_own: Vec<Loc> = Loc::get_all_on_board(5, 5)
+            .into_iter()
+            .filter(|loc| loc.is_on_board((5, 5)) && *loc != Loc { row: 2, col: 2 })
+            .collect();
+        assert_eq!(white.opponent_stones_in(&everything_but_own), (1, 1));
+        assert_eq!(white.opponent_stones_in(&[Loc { row: 1, col: 1 }]), (0, 1));
+        assert_eq!(white.opponent_stones_in(&[Loc { row: 2, col: 2 }]), (0, 0));
+    }
+}
@@ -0,0 +1,21 @@
+pub mod agent;
+pub mod belief;
+pub mod board;
+pub mod chain;
+pub mod clock;
+pub mod gtp;
+pub mod knowledge;
+pub mod match_runner;
+pub mod observer;
+pub mod ownership;
+pub mod policy;
+pub mod referee;
+pub mod seed;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod symmetry;
+pub mod tactics;
+pub mod tournament;
+pub mod tsumego;
+pub mod tui;
+pub mod variant;
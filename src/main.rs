@@ -1,14 +1,258 @@
-use crate::board::{Board, Loc, Move};
-
-pub mod board;
+use go_board::agent::{Agent, PassAgent, RandomAgent};
+use go_board::belief::BeliefMap;
+use go_board::board;
+use go_board::board::{Board, GameResult, Loc, Move, MoveKind, Player};
+use go_board::clock::{Clock, SystemTime, TimeControl};
+use go_board::gtp::{self, Engine};
+use go_board::knowledge::Known;
+use go_board::match_runner::MatchConfig;
+use go_board::observer::ObserverView;
+use go_board::ownership::estimate_view_ownership;
+use go_board::referee::{Probe, ProbeRules, Referee};
+use go_board::seed::new_rng;
+use go_board::tournament::{Schedule, Tournament};
+use go_board::tui;
+use go_board::variant::Variant;
+use rand::Rng;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
 
 fn main() {
-    let mut board = Board::new(7, 7, 1.5);
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("tournament") => run_tournament(&args[2..]),
+        Some("phantom") => play_hot_seat_game(&args[2..]),
+        Some("gtp") => run_gtp(&args[2..]),
+        Some(name) => match Variant::from_name(name) {
+            Some(variant) => start_local_game(variant, &args[2..]),
+            None => {
+                println!("Unknown mode, try standard, one-color, blind, phantom, tournament or gtp")
+            }
+        },
+        None => play_local_game(Variant::Standard, TimeControl::Unlimited),
+    }
+}
+
+fn clear_screen() {
+    print!("\x1B[2J\x1B[1;1H");
+}
+
+// Referee messages for the player that were announced since the player last looked
+fn print_new_messages(referee: &Referee, player: Player, seen: &mut usize) {
+    for announcement in &referee.get_log()[*seen..] {
+        if announcement.is_for(player) {
+            println!("Referee: {}", announcement.describe_for(player));
+        }
+    }
+    *seen = referee.get_log().len();
+}
+
+fn print_knowledge_summary(referee: &Referee, player: Player) {
+    let knowledge = referee.get_view(player).get_knowledge();
+    let (rows, cols) = referee.get_board().board_size();
+    let located = Loc::get_all_on_board(rows, cols)
+        .into_iter()
+        .filter(|loc| knowledge.get(*loc) == Known::Opponent)
+        .count();
+    println!(
+        "{:?} has {} stone(s) on the board, you know where {} of them are\n",
+        player.opponent(),
+        knowledge.opponent_stone_count(),
+        located
+    );
+}
+
+// Phantom Go for two humans sharing one terminal, each sees only their own view.
+// Usage: phantom [seed], the seed of the estimates so they replay with the game
+fn play_hot_seat_game(args: &[String]) {
+    let seed = args
+        .first()
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = new_rng(seed);
+    let komi = 1.5;
+    let mut referee = Referee::new(7, 7, komi);
+    referee.set_probe_rules(Some(ProbeRules::default()));
+    let mut black_seen = 0;
+    let mut white_seen = 0;
 
     // Game loop
-    while !board.last_two_moves_are_pass() {
+    while !referee.is_over() {
+        let player = referee.get_current_player();
+        let seen = match player {
+            Player::Black => &mut black_seen,
+            Player::White => &mut white_seen,
+        };
+
+        clear_screen();
+        println!("Pass the keyboard to {:?} and press Enter", player);
+        board::take_player_input();
+
+        let mut note = String::new();
+        while referee.get_current_player() == player && !referee.is_over() {
+            clear_screen();
+            println!("Turn: {:?}\n", player);
+            println!("{}", referee.get_view(player));
+            print_knowledge_summary(&referee, player);
+            print_new_messages(&referee, player, seen);
+            println!("{}", note);
+            println!("Input coordinates to play, 'p' to pass, 'r' to resign, 'b' to see where the opponent likely played, 'o' to see who likely owns what or 'q' to quit");
+            println!("Ask the referee with '? row, col' if a point is occupied (1 point) or '?' if you can capture (free)");
+
+            let player_input = board::take_player_input();
+            note.clear();
+            match player_input.as_str() {
+                "q" => {
+                    println!("\nQuit game!\n\n{}", GameResult::Void);
+                    return;
+                }
+                "p" => {
+                    referee.pass();
+                }
+                "r" => {
+                    referee.resign();
+                }
+                "b" => {
+                    let knowledge = referee.get_view(player).get_knowledge();
+                    let beliefs = BeliefMap::estimate(knowledge, 500, &mut rng);
+                    note = format!(
+                        "Chance of an opponent stone, in tenths:\n{}",
+                        beliefs.to_colored_string()
+                    );
+                }
+                "o" => {
+                    let view = referee.get_view(player);
+                    let ownership = estimate_view_ownership(view, komi, 200, &mut rng);
+                    note = format!(
+                        "Likely owners, red for Black and blue for White, Black ahead by {:.1}:\n{}",
+                        ownership.get_expected_score(),
+                        ownership.to_colored_string()
+                    );
+                }
+                "?" => {
+                    referee.probe(Probe::AnyCapture);
+                }
+                _ if player_input.starts_with('?') => match Loc::from_string(&player_input[1..]) {
+                    None => note = String::from("Invalid question :c"),
+                    Some(loc) => {
+                        referee.probe(Probe::Occupied(loc));
+                    }
+                },
+                _ => match Loc::from_string(&player_input) {
+                    None => note = String::from("Invalid move :c"),
+                    Some(loc) => {
+                        referee.attempt(loc);
+                    }
+                },
+            }
+        }
+
+        clear_screen();
+        println!("{}", referee.get_view(player));
+        print_new_messages(&referee, player, seen);
+        println!("\nPress Enter to end your turn");
+        board::take_player_input();
+    }
+
+    clear_screen();
+    let observer = ObserverView::new(&referee);
+    println!(
+        "Game over!\n\n{}\n{}\nSeed: {}",
+        observer.kibitz_log(),
+        observer,
+        seed
+    );
+    let result = referee.count_score();
+    println!("{}\nResult: {}", result, result.to_sgf());
+}
+
+// Usage: gtp [seed], commands come on stdin and responses go to stdout
+fn run_gtp(args: &[String]) {
+    let seed = args
+        .first()
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| rand::thread_rng().gen());
+    let mut engine = Engine::new(Box::new(SystemTime::new()), seed);
+    if let Err(error) = gtp::run(&mut engine, io::stdin().lock(), io::stdout()) {
+        eprintln!("GTP failed: {}", error);
+    }
+}
+
+// Usage: tournament [round-robin|gauntlet] [games per pairing] [crosstable file] [seed]
+fn run_tournament(args: &[String]) {
+    let schedule = match args.first().map(|arg| arg.as_str()) {
+        Some("gauntlet") => Schedule::Gauntlet,
+        _ => Schedule::RoundRobin,
+    };
+    let games_per_pairing = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(10);
+    let seed = args
+        .get(3)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| rand::thread_rng().gen());
+
+    let mut agents: Vec<Box<dyn Agent>> = vec![
+        Box::new(RandomAgent::new(seed)),
+        Box::new(RandomAgent::with_pass_probability(0.1, seed)),
+        Box::new(PassAgent),
+    ];
+    let tournament = Tournament::new(
+        schedule,
+        games_per_pairing,
+        MatchConfig::new(11, 11, 7.5),
+        seed,
+    );
+    let result = tournament.run(&mut agents);
+    let crosstable = format!("Seed: {}\n\n{}", seed, result);
+
+    println!("{}", crosstable);
+    if let Some(path) = args.get(2) {
+        let games: Vec<String> = result
+            .records
+            .iter()
+            .map(|record| record.to_string())
+            .collect();
+        fs::write(path, format!("{}\n{}\n", crosstable, games.join("\n")))
+            .expect("Failed to write the crosstable");
+    }
+}
+
+// Usage: <variant> [none | absolute main | byoyomi main period periods | canadian main period stones | fischer main increment]
+fn start_local_game(variant: Variant, args: &[String]) {
+    if args.is_empty() {
+        return play_local_game(variant, TimeControl::Unlimited);
+    }
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    match TimeControl::from_kgs(&args) {
+        Some(time_control) => play_local_game(variant, time_control),
+        None => println!("Unknown time control, times are in seconds, e.g. byoyomi 600 30 5"),
+    }
+}
+
+fn play_local_game(variant: Variant, time_control: TimeControl) {
+    let board = Board::new(7, 7, 1.5);
+    let clock = Clock::new(time_control, Box::new(SystemTime::new()));
+    // Scripted input can't drive the full-screen UI
+    if !io::stdin().is_terminal() {
+        return play_line_game(board, variant, clock);
+    }
+    match tui::run(board, variant, clock) {
+        Ok(result) => println!("{}\nResult: {}", result, result.to_sgf()),
+        Err(error) => println!("The terminal UI failed: {}", error),
+    }
+}
+
+fn play_line_game(mut board: Board, variant: Variant, mut clock: Clock) {
+    let mut result = None;
+    clock.start(board.get_current_player());
+
+    // Game loop
+    while !board.is_over() {
+        if clock.get_control() != TimeControl::Unlimited {
+            println!("Clock: {}", clock);
+        }
         println!(
-            "Turn: {:?}\nInput coordinates to play, 'u' to undo, 'p' to pass or 'q' to quit",
+            "Turn: {:?}\nInput coordinates to play, 'u' to undo, 'p' to pass, 'r' to resign or 'q' to quit",
             board.get_current_player()
         );
         let player_input = board::take_player_input();
@@ -16,30 +260,67 @@ fn main() {
         match player_input.as_str() {
             "q" => {
                 println!("\nQuit game!\n");
-                break
+                result = Some(GameResult::Void);
+                break;
+            }
+            "r" => {
+                board.play(&Move {
+                    player: board.get_current_player(),
+                    kind: MoveKind::Resign,
+                });
+            }
+            "p" => {
+                let mv = Move {
+                    player: board.get_current_player(),
+                    kind: MoveKind::Pass,
+                };
+                board.play(&mv);
+                println!("{}", Variant::announce(&mv));
+                if !clock.stop() {
+                    result = Some(GameResult::Time(mv.player.opponent()));
+                    break;
+                }
+                clock.start(board.get_current_player());
             }
-            "p" => board.play(&Move {
-                player: board.get_current_player(),
-                loc: Loc::pass(),
-            }),
             "gh" => {
                 println!("\n\n{:?}\n\n", board.get_game_history());
             }
             "u" => {
+                let waiting = board.get_current_player();
                 board = board.undo();
+                println!("The last move was taken back");
+                // The time spent before asking for the undo still counts
+                if !clock.start(board.get_current_player()) {
+                    result = Some(GameResult::Time(waiting.opponent()));
+                    break;
+                }
             }
             _ => match Loc::from_string(&player_input) {
                 None => {
                     println!("\nInvalid move :c\nT R Y  A G A I N !\n");
                 }
                 Some(loc) => {
-                    let player = board.get_current_player()
-                    board = board.play(Move { player, loc });
+                    let player = board.get_current_player();
+                    let mv = Move::new(player, loc);
+                    if board.move_is_valid(&mv) {
+                        board.play(&mv);
+                        println!("{}", Variant::announce(&mv));
+                        if !clock.stop() {
+                            result = Some(GameResult::Time(player.opponent()));
+                            break;
+                        }
+                        clock.start(board.get_current_player());
+                    } else {
+                        println!("\nIllegal move, try again\n");
+                    }
                 }
             },
         }
-        println!("{}", board.to_string());
+        if let Some(board_string) = variant.render(&board) {
+            println!("{}", board_string);
+        }
     }
 
-    println!("{}", board.count_score().to_string());
+    let result = result.unwrap_or_else(|| board.count_score());
+    println!("{}\nResult: {}", result, result.to_sgf());
 }
@@ -0,0 +1,284 @@
+use crate::agent::Agent;
+use crate::board::{GameResult, Move, MoveKind, Player};
+use crate::clock::{Clock, SystemTime, TimeControl, TimeSource};
+use crate::referee::{Announcement, AttemptRules, Event, ProbeRules, Referee};
+use crate::seed::derive_seed;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct MatchConfig {
+    pub rows: usize,
+    pub cols: usize,
+    pub komi: f32,
+    // Games reaching this many moves are scored as they stand
+    pub max_moves: usize,
+    // After this many rejected attempts in one turn the player passes
+    pub max_attempts_per_turn: usize,
+    // Probes count as attempts, None when they aren't allowed
+    pub probe_rules: Option<ProbeRules>,
+    // Limits the referee puts on rejected attempts, on top of max_attempts_per_turn
+    pub attempt_rules: AttemptRules,
+    // A player whose flag falls loses on time
+    pub time_control: TimeControl,
+}
+
+impl MatchConfig {
+    // Sizes include sentinels, like in Board::new
+    pub fn new(rows: usize, cols: usize, komi: f32) -> Self {
+        MatchConfig {
+            rows,
+            cols,
+            komi,
+            max_moves: 3 * rows * cols,
+            max_attempts_per_turn: rows * cols,
+            probe_rules: None,
+            attempt_rules: AttemptRules::default(),
+            time_control: TimeControl::Unlimited,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub black: String,
+    pub white: String,
+    // Replaying with the same agents, config and seed gives the same game
+    pub seed: u64,
+    pub result: GameResult,
+    pub moves: Vec<Move>,
+    pub announcements: Vec<Announcement>,
+}
+
+impl GameRecord {
+    pub fn winner(&self) -> Option<Player> {
+        self.result.get_winner()
+    }
+}
+
+// One line for match logs, the result is written like SGF RE so the reason is visible
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} vs {}: {} after {} move(s), seed {}",
+            self.black,
+            self.white,
+            self.result.to_sgf(),
+            self.moves.len(),
+            self.seed
+        )
+    }
+}
+
+fn tell(agent: &mut dyn Agent, player: Player, announcements: &[Announcement]) {
+    for announcement in announcements {
+        if announcement.is_for(player) {
+            agent.observe(announcement);
+        }
+    }
+}
+
+pub fn play_game(
+    black: &mut dyn Agent,
+    white: &mut dyn Agent,
+    config: &MatchConfig,
+    seed: u64,
+) -> GameRecord {
+    play_game_with_time(black, white, config, seed, Box::new(SystemTime::new()))
+}
+
+// Games that run into time trouble are only reproducible with a mock time source
+pub fn play_game_with_time(
+    black: &mut dyn Agent,
+    white: &mut dyn Agent,
+    config: &MatchConfig,
+    seed: u64,
+    time: Box<dyn TimeSource>,
+) -> GameRecord {
+    let mut clock = Clock::new(config.time_control, time);
+    let mut result = None;
+    let mut referee = Referee::new(config.rows, config.cols, config.komi);
+    referee.set_probe_rules(config.probe_rules);
+    referee.set_attempt_rules(config.attempt_rules);
+    black.new_game(derive_seed(seed, 0));
+    white.new_game(derive_seed(seed, 1));
+
+    while !referee.is_over() && referee.get_board().get_game_history().len() < config.max_moves {
+        let player = referee.get_current_player();
+        let mut attempts = 0;
+        match player {
+            Player::Black => black.set_time_budget(clock.move_budget(player)),
+            Player::White => white.set_time_budget(clock.move_budget(player)),
+        }
+        clock.start(player);
+        loop {
+            let agent: &mut dyn Agent = match player {
+                Player::Black => &mut *black,
+                Player::White => &mut *white,
+            };
+            let probe = match config.probe_rules {
+                Some(_) => agent.probe(referee.get_view(player)),
+                None => None,
+            };
+            let mut announcements = match probe {
+                Some(probe) => referee.probe(probe),
+                None => match agent.gen_move(referee.get_view(player)) {
+                    MoveKind::Play(loc) => referee.attempt(loc),
+                    MoveKind::Pass => referee.pass(),
+                    MoveKind::Resign => referee.resign(),
+                },
+            };
+            attempts += 1;
+
+            let turn_is_over = announcements
+                .iter()
+                .any(|a| matches!(a.event, Event::Played(_) | Event::Passed | Event::Resigned));
+            if !turn_is_over && attempts >= config.max_attempts_per_turn {
+                announcements.extend(referee.pass());
+            }
+
+            tell(black, Player::Black, &announcements);
+            tell(white, Player::White, &announcements);
+            if referee.get_current_player() != player || referee.is_over() {
+                break;
+            }
+        }
+        if !clock.stop() {
+            result = Some(GameResult::Time(player.opponent()));
+            break;
+        }
+    }
+
+    GameRecord {
+        black: black.name(),
+        white: white.name(),
+        seed,
+        result: result.unwrap_or_else(|| referee.final_score()),
+        moves: referee.get_board().get_game_history().clone(),
+        announcements: referee.get_log().clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{Agent, PassAgent, RandomAgent};
+    use crate::board::{GameResult, Loc, MoveKind, Player};
+    use crate::clock::{MockTime, TimeControl};
+    use crate::match_runner::{play_game, play_game_with_time, MatchConfig};
+    use crate::referee::{PlayerView, Probe, ProbeRules};
+    use std::time::Duration;
+
+    // Never plays, only asks about the corner
+    struct ProbeAgent;
+
+    impl Agent for ProbeAgent {
+        fn name(&self) -> String {
+            String::from("probe")
+        }
+
+        fn gen_move(&mut self, _view: &PlayerView) -> MoveKind {
+            MoveKind::Pass
+        }
+
+        fn probe(&mut self, _view: &PlayerView) -> Option<Probe> {
+            Some(Probe::Occupied(Loc { row: 1, col: 1 }))
+        }
+    }
+
+    // Plays like RandomAgent, but every move takes the given time
+    struct SlowAgent {
+        inner: RandomAgent,
+        time: MockTime,
+        think: Duration,
+        budgets: Vec<Duration>,
+    }
+
+    impl Agent for SlowAgent {
+        fn name(&self) -> String {
+            String::from("slow")
+        }
+
+        fn set_time_budget(&mut self, budget: Duration) {
+            self.budgets.push(budget);
+        }
+
+        fn gen_move(&mut self, view: &PlayerView) -> MoveKind {
+            self.time.advance(self.think);
+            self.inner.gen_move(view)
+        }
+    }
+
+    #[test]
+    fn games_between_random_agents_finish() {
+        let config = MatchConfig::new(7, 7, 0.5);
+        let mut black = RandomAgent::new(1);
+        let mut white = RandomAgent::new(2);
+
+        let record = play_game(&mut black, &mut white, &config, 3);
+        assert!(record.moves.len() <= config.max_moves);
+        assert_eq!(record.black, "random");
+        assert_eq!(record.seed, 3);
+    }
+
+    #[test]
+    fn games_are_reproduced_from_seed_and_config() {
+        let config = MatchConfig::new(9, 9, 0.5);
+        let mut black = RandomAgent::new(0);
+        let mut white = RandomAgent::with_pass_probability(0.01, 0);
+
+        let first = play_game(&mut black, &mut white, &config, 2024);
+        let other = play_game(&mut black, &mut white, &config, 2025);
+        let replay = play_game(&mut black, &mut white, &config, 2024);
+        assert_eq!(first.moves, replay.moves);
+        assert_eq!(first.announcements, replay.announcements);
+        assert_eq!(first.result, replay.result);
+        assert_ne!(first.moves, other.moves);
+    }
+
+    #[test]
+    fn komi_decides_a_game_without_stones() {
+        let config = MatchConfig::new(7, 7, 0.5);
+        let record = play_game(&mut PassAgent, &mut PassAgent, &config, 0);
+        assert_eq!(record.moves.len(), 2);
+        assert_eq!(record.winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn probes_count_as_attempts_and_their_cost_goes_to_the_opponent() {
+        let mut config = MatchConfig::new(7, 7, 0.5);
+        config.max_attempts_per_turn = 3;
+        config.probe_rules = Some(ProbeRules::default());
+
+        let record = play_game(&mut ProbeAgent, &mut PassAgent, &config, 0);
+        assert_eq!(record.moves.len(), 2);
+        assert_eq!(record.result, GameResult::Player(Player::White, 3.5));
+
+        config.probe_rules = None;
+        let record = play_game(&mut ProbeAgent, &mut PassAgent, &config, 0);
+        assert_eq!(record.result, GameResult::Player(Player::White, 0.5));
+    }
+
+    #[test]
+    fn a_player_whose_flag_falls_loses_on_time() {
+        let mut config = MatchConfig::new(7, 7, 0.5);
+        config.time_control = TimeControl::Absolute {
+            main: Duration::from_secs(60),
+        };
+        let time = MockTime::new();
+        let mut black = SlowAgent {
+            inner: RandomAgent::new(1),
+            time: time.clone(),
+            think: Duration::from_secs(40),
+            budgets: vec![],
+        };
+        let mut white = RandomAgent::new(2);
+
+        let record = play_game_with_time(&mut black, &mut white, &config, 0, Box::new(time));
+        assert_eq!(record.result, GameResult::Time(Player::White));
+        assert_eq!(record.moves.len(), 3);
+        assert_eq!(
+            black.budgets,
+            vec![Duration::from_secs(3), Duration::from_secs(1)]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+// Full information view of a phantom game for spectators and post-game analysis.
+// Lingo:
+//     overlay    - the true board as one player knows it
+//     discovered - an opponent stone the player's view shows
+//     hidden     - an opponent stone the player's view doesn't show
+
+use crate::board::{Loc, Player};
+use crate::referee::{Event, Referee};
+use std::fmt;
+
+const HIDDEN: &str = "?";
+
+pub struct ObserverView<'a> {
+    referee: &'a Referee,
+}
+
+impl<'a> ObserverView<'a> {
+    pub fn new(referee: &'a Referee) -> Self {
+        ObserverView { referee }
+    }
+
+    // Discovered and all opponent stones on the board
+    pub fn discovered(&self, player: Player) -> (usize, usize) {
+        let board = self.referee.get_board();
+        let view = self.referee.get_view(player);
+        let opponent = player.opponent().to_color();
+        let (rows, cols) = board.board_size();
+
+        let opponent_stones: Vec<Loc> = Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| board.get(*loc) == opponent)
+            .collect();
+        let discovered = opponent_stones
+            .iter()
+            .filter(|loc| view.get(**loc) == opponent)
+            .count();
+        (discovered, opponent_stones.len())
+    }
+
+    fn overlay_row(&self, player: Player, row: usize) -> String {
+        let board = self.referee.get_board();
+        let view = self.referee.get_view(player);
+        let (_, cols) = board.board_size();
+
+        let mut overlay = String::new();
+        for col in 0..cols {
+            let loc = Loc { row, col };
+            let color = board.get(loc);
+            if color == player.opponent().to_color() && view.get(loc) != color {
+                overlay.push_str(HIDDEN);
+            } else {
+                overlay.push_str(&color.to_string());
+            }
+            overlay.push(' ');
+        }
+        overlay
+    }
+
+    // Every attempt in order, numbered, with the captures it caused below it
+    pub fn kibitz_log(&self) -> String {
+        let mut log = String::new();
+        let mut attempt = 0;
+        for announcement in self.referee.get_log() {
+            match announcement.event {
+                // Say the same as Played and Occupied or Illegal, from the opponent's side
+                Event::Moved | Event::Rejected(_) => (),
+                Event::Captured(_, _) | Event::Penalized(_) | Event::Forfeited => {
+                    log += &format!("{:>6}{}\n", "", announcement)
+                }
+                _ => {
+                    attempt += 1;
+                    log += &format!("{:>4}. {}\n", attempt, announcement);
+                }
+            }
+        }
+        log
+    }
+}
+
+// The true board next to Black's and White's overlays
+impl fmt::Display for ObserverView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let board = self.referee.get_board();
+        let (rows, cols) = board.board_size();
+        let width = 2 * cols;
+
+        writeln!(
+            f,
+            "{:<width$}   {:<width$}   White knows",
+            "Board", "Black knows"
+        )?;
+        for row in 0..rows {
+            let mut true_row = String::new();
+            for col in 0..cols {
+                true_row.push_str(&format!("{} ", board.get(Loc { row, col })));
+            }
+            let line = format!(
+                "{:<width$}   {:<width$}   {}",
+                true_row,
+                self.overlay_row(Player::Black, row),
+                self.overlay_row(Player::White, row)
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        for player in [Player::Black, Player::White] {
+            let (discovered, total) = self.discovered(player);
+            writeln!(
+                f,
+                "{:?} has discovered {} of {} {:?} stone(s), hidden ones are marked {}",
+                player,
+                discovered,
+                total,
+                player.opponent(),
+                HIDDEN
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Loc, Player};
+    use crate::observer::ObserverView;
+    use crate::referee::Referee;
+
+    #[test]
+    fn overlays_mark_hidden_and_discovered_opponent_stones() {
+        let mut referee = Referee::new(5, 5, 0.5);
+        referee.attempt(Loc { row: 1, col: 1 });
+        referee.attempt(Loc { row: 1, col: 1 });
+        referee.attempt(Loc { row: 3, col: 3 });
+        referee.attempt(Loc { row: 2, col: 2 });
+
+        let observer = ObserverView::new(&referee);
+        assert_eq!(observer.discovered(Player::White), (1, 2));
+        assert_eq!(observer.discovered(Player::Black), (0, 1));
+
+        let lines: Vec<String> = observer.to_string().lines().map(String::from).collect();
+        assert_eq!(lines[2], "/ O . . /    / O . . /    / O . . /");
+        assert_eq!(lines[3], "/ . O . /    / . O . /    / . ? . /");
+        assert_eq!(lines[4], "/ . . # /    / . . ? /    / . . # /");
+    }
+
+    #[test]
+    fn kibitz_log_numbers_every_attempt() {
+        let mut referee = Referee::new(5, 5, 0.5);
+        referee.attempt(Loc { row: 1, col: 1 });
+        referee.attempt(Loc { row: 1, col: 1 });
+        referee.attempt(Loc { row: 0, col: 0 });
+        referee.pass();
+
+        let log = ObserverView::new(&referee).kibitz_log();
+        assert_eq!(
+            log,
+            "   1. Black played 1, 1\n   2. White tried 1, 1: occupied\n   3. White tried 0, 0: illegal\n   4. White passed\n"
+        );
+    }
+}
@@ -0,0 +1,291 @@
+// Who is likely to end up owning each point, and by how much Black is likely to win, from playouts.
+// Lingo:
+//     ownership      - from 1 for a point Black owns at the end of every playout to -1 for one White always owns
+//     expected score - Black's average margin at the end of the playouts, negative when White is ahead
+//     dead stone     - a stone whose point the opponent owns at the end of most playouts
+// Playouts use the default policy and are scored by area, stones and territory minus komi, because they fill
+// in their own territory, where captures and territory alone would count less the longer they run.
+// For phantom games every playout starts from a new determinization of what the player knows.
+
+use crate::belief::sample_determinization;
+use crate::board::{Board, Color, Loc, Player};
+use crate::policy::Policy;
+use crate::referee::PlayerView;
+use crate::seed::GameRng;
+use std::fmt;
+
+// Ownership beyond this counts as owned, for dead stones and the digits of the map
+const OWNED: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct OwnershipMap {
+    ownership: Vec<Vec<f64>>,
+    // Stones the estimate started from and sentinels, only own and discovered stones for phantom games
+    fields: Vec<Vec<Color>>,
+    expected_score: f64,
+    playouts: usize,
+}
+
+// Playouts long enough to fill the board a few times over, longer ones only come from repeated captures
+fn max_moves(board: &Board) -> usize {
+    let (rows, cols) = board.board_size();
+    3 * rows * cols
+}
+
+impl OwnershipMap {
+    fn new(fields: Vec<Vec<Color>>) -> Self {
+        let ownership = vec![vec![0.0; fields[0].len()]; fields.len()];
+        OwnershipMap {
+            ownership,
+            fields,
+            expected_score: 0.0,
+            playouts: 0,
+        }
+    }
+
+    // Adds the owners and the score of the finished playout, averaged by finish
+    fn add(&mut self, played: &Board) {
+        for (row, owners) in played.get_owners().iter().enumerate() {
+            for (col, owner) in owners.iter().enumerate() {
+                self.ownership[row][col] += match owner {
+                    Color::Black => 1.0,
+                    Color::White => -1.0,
+                    _ => 0.0,
+                };
+            }
+        }
+        self.expected_score += played.area_score().black_margin().unwrap_or(0.0) as f64;
+        self.playouts += 1;
+    }
+
+    fn finish(mut self, komi: f64) -> Self {
+        if self.playouts > 0 {
+            let playouts = self.playouts as f64;
+            for row in self.ownership.iter_mut() {
+                for ownership in row.iter_mut() {
+                    *ownership /= playouts;
+                }
+            }
+            self.expected_score = self.expected_score / playouts - komi;
+        }
+        self
+    }
+
+    pub fn get(&self, loc: Loc) -> f64 {
+        self.ownership[loc.row][loc.col]
+    }
+
+    pub fn get_expected_score(&self) -> f64 {
+        self.expected_score
+    }
+
+    // The playouts the estimate is made of, fewer than asked for when determinizations couldn't be sampled
+    pub fn get_playouts(&self) -> usize {
+        self.playouts
+    }
+
+    // The player most playouts give the point to, None when neither owns it often enough
+    pub fn get_owner(&self, loc: Loc) -> Option<Player> {
+        let ownership = self.get(loc);
+        if ownership >= OWNED {
+            Some(Player::Black)
+        } else if ownership <= -OWNED {
+            Some(Player::White)
+        } else {
+            None
+        }
+    }
+
+    // Stones on points the opponent owns, for marking dead stones before counting
+    pub fn dead_stones(&self) -> Vec<Loc> {
+        let (rows, cols) = (self.fields.len(), self.fields[0].len());
+        Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| {
+                matches!(
+                    (self.fields[loc.row][loc.col], self.get_owner(*loc)),
+                    (Color::Black, Some(Player::White)) | (Color::White, Some(Player::Black))
+                )
+            })
+            .collect()
+    }
+
+    // Heat map with ANSI background colors, from blue for White's points to red for Black's
+    pub fn to_colored_string(&self) -> String {
+        let mut heat_map = String::new();
+        for (row, fields) in self.fields.iter().enumerate() {
+            for (col, field) in fields.iter().enumerate() {
+                if *field == Color::Invalid {
+                    heat_map += &format!("{} ", field);
+                    continue;
+                }
+                let level = ((self.ownership[row][col] + 1.0) * 2.5).round() as u8;
+                let color = 16 + 36 * level + (5 - level);
+                heat_map += &format!("\x1B[48;5;{}m{} \x1B[0m", color, field);
+            }
+            heat_map += "\n";
+        }
+        heat_map
+    }
+}
+
+// b and w for empty points one player owns, x for dead stones
+impl fmt::Display for OwnershipMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dead = self.dead_stones();
+        for (row, fields) in self.fields.iter().enumerate() {
+            for (col, field) in fields.iter().enumerate() {
+                let loc = Loc { row, col };
+                match (field, self.get_owner(loc)) {
+                    _ if dead.contains(&loc) => write!(f, "x ")?,
+                    (Color::Empty, Some(Player::Black)) => write!(f, "b ")?,
+                    (Color::Empty, Some(Player::White)) => write!(f, "w ")?,
+                    _ => write!(f, "{} ", field)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Plays the position out n_playouts times with the player to move starting
+pub fn estimate_ownership(board: &Board, n_playouts: usize, rng: &mut GameRng) -> OwnershipMap {
+    let policy = Policy::default();
+    let (rows, cols) = board.board_size();
+    let mut fields = vec![vec![Color::Invalid; cols]; rows];
+    for loc in Loc::get_all_on_board(rows, cols) {
+        fields[loc.row][loc.col] = board.get(loc);
+    }
+    let mut map = OwnershipMap::new(fields);
+    for _ in 0..n_playouts {
+        map.add(&policy.playout(board, max_moves(board), rng));
+    }
+    map.finish(0.0)
+}
+
+// The same for a phantom player, averaged over determinizations of what the view knows.
+// Determinizations know neither komi nor the penalties so far, those are added to the expected score here
+pub fn estimate_view_ownership(
+    view: &PlayerView,
+    komi: f32,
+    n_playouts: usize,
+    rng: &mut GameRng,
+) -> OwnershipMap {
+    let policy = Policy::default();
+    let (rows, cols) = view.board_size();
+    let mut fields = vec![vec![Color::Invalid; cols]; rows];
+    for loc in Loc::get_all_on_board(rows, cols) {
+        fields[loc.row][loc.col] = view.get(loc);
+    }
+    let mut map = OwnershipMap::new(fields);
+    for _ in 0..n_playouts {
+        if let Some(board) = sample_determinization(view.get_knowledge(), rng) {
+            map.add(&policy.playout(&board, max_moves(&board), rng));
+        }
+    }
+    map.finish((komi - view.get_knowledge().black_penalty_margin()) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, Player};
+    use crate::ownership::{estimate_ownership, estimate_view_ownership};
+    use crate::referee::{Probe, ProbeRules, Referee};
+    use crate::seed::new_rng;
+
+    #[test]
+    fn walls_split_the_board_between_their_owners() {
+        let board =
+            Board::from_diagram(". O . # .|. O . # .|. O . # .|. O . # .|. O . # .; komi 0.5")
+                .unwrap();
+        let map = estimate_ownership(&board, 50, &mut new_rng(2));
+        assert_eq!(map.get_playouts(), 50);
+        assert!(map.get(Loc { row: 3, col: 1 }) > 0.5);
+        assert!(map.get(Loc { row: 3, col: 5 }) < -0.5);
+        assert_eq!(map.get_owner(Loc { row: 1, col: 2 }), Some(Player::Black));
+        assert!(map.dead_stones().is_empty());
+        // The middle column is shared out in the playouts, the rest is even
+        assert!(map.get_expected_score().abs() < 5.5);
+    }
+
+    #[test]
+    fn the_expected_score_follows_uneven_territory() {
+        // Both groups have three eyes, Black's area is 30 points and White's 12 however the playouts fill them in
+        let rows = [". . O . O # .", ". . O O O # #"].repeat(3).join("|");
+        let board = Board::from_diagram(&format!("{}; komi 0.5", rows)).unwrap();
+        assert_eq!(board.area_score().black_margin(), Some(17.5));
+        let map = estimate_ownership(&board, 200, &mut new_rng(5));
+        assert!((map.get_expected_score() - 17.5).abs() < 1.0);
+    }
+
+    #[test]
+    fn a_stone_inside_enemy_territory_is_dead() {
+        // Both sides have eyes that playouts never fill
+        let board = Board::from_diagram(
+            "Black to play, komi 0.5
+             . . O O # . #
+             . # . O # # .
+             . . O O # . #
+             . O . O # # .
+             O . O O # . #",
+        )
+        .unwrap();
+        let map = estimate_ownership(&board, 100, &mut new_rng(4));
+        assert_eq!(map.dead_stones(), vec![Loc { row: 2, col: 2 }]);
+        // 20 points for Black once the White stone is taken, 15 for White
+        assert!((map.get_expected_score() - 4.5).abs() < 1.0);
+        assert_eq!(
+            map.to_string().lines().nth(2).unwrap(),
+            "/ b x b O # # w / "
+        );
+    }
+
+    #[test]
+    fn phantom_estimates_only_show_what_the_player_knows() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        for loc in [(2, 2), (4, 4), (2, 4), (3, 3)] {
+            referee.attempt(Loc {
+                row: loc.0,
+                col: loc.1,
+            });
+        }
+        let view = referee.get_view(Player::Black);
+        let map = estimate_view_ownership(view, 0.5, 20, &mut new_rng(3));
+        assert_eq!(map.get_playouts(), 20);
+        assert!(map.get(Loc { row: 2, col: 2 }) > 0.0);
+        // White's stones are hidden from Black
+        assert!(!map.to_string().contains('#'));
+    }
+
+    #[test]
+    fn phantom_expected_scores_count_the_penalties_so_far() {
+        let mut referee = Referee::new(5, 5, 0.5);
+        referee.set_probe_rules(Some(ProbeRules::default()));
+        referee.probe(Probe::Occupied(Loc { row: 1, col: 1 }));
+        // Black fills the board but for two eyes, taking White's stone on 3 3 on the way
+        for loc in [
+            (2, 2),
+            (3, 3),
+            (3, 2),
+            (2, 3),
+            (1, 2),
+            (1, 3),
+            (2, 1),
+            (3, 1),
+        ] {
+            if referee.get_board().get_current_player() == Player::White && loc != (3, 3) {
+                referee.pass();
+            }
+            referee.attempt(Loc {
+                row: loc.0,
+                col: loc.1,
+            });
+        }
+        let view = referee.get_view(Player::Black);
+        assert_eq!(view.get_knowledge().black_penalty_margin(), -1.0);
+        // Nobody can play any more, Black's nine points against komi and the probe
+        let map = estimate_view_ownership(view, 0.5, 5, &mut new_rng(1));
+        assert_eq!(map.get_expected_score(), 7.5);
+    }
+}
@@ -0,0 +1,373 @@
+// Move choice for playouts: random, but weighted towards moves that usually matter.
+// Lingo:
+//     playout    - a game played on by the policy until two passes, to see who ends up owning what
+//     weight     - how likely a move is to be picked, moves are drawn in proportion to their weights
+//     escape     - a move that gives an own group in atari a second liberty
+//     self-atari - a move that leaves its own group with a single liberty without capturing anything
+//     pattern    - the 3x3 neighbourhood of a point, matched around the last move only
+// Patterns are seen from the player to move: X own stone, O opponent stone, . empty, # off the board,
+// x anything but an own stone, o anything but an opponent stone, ? anything.
+// The center is the move and always empty, rows are separated by '|' or new lines.
+// Every pattern matches in all 8 symmetries and with the colors swapped.
+
+use crate::board::{Board, Color, Loc, Move, MoveKind};
+use crate::seed::GameRng;
+use crate::symmetry::Symmetry;
+use rand::Rng;
+
+// Hane, cuts and edge moves of MoGo
+pub const DEFAULT_PATTERNS: [&str; 11] = [
+    "XOX|...|???",
+    "XO.|...|?.?",
+    "XO?|X..|x.?",
+    ".O.|X..|...",
+    "XO?|O.o|?o?",
+    "?X?|O.O|ooo",
+    "X.?|O.?|###",
+    "OX?|o.O|###",
+    "?X?|o.O|###",
+    "?XO|o.o|###",
+    "?OX|X.O|###",
+];
+
+// Own, opponent and off the board take the values 1 to 3 in the pattern codes
+const CODES: usize = 1 << 16;
+
+// The neighbours of a point in pattern order, top row first
+const OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Added up for every move, the self-atari weight multiplies the sum
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolicyWeights {
+    // Every legal move that doesn't fill an own eye
+    pub base: f64,
+    pub capture: f64,
+    pub escape: f64,
+    pub pattern: f64,
+    pub self_atari: f64,
+}
+
+impl Default for PolicyWeights {
+    fn default() -> Self {
+        PolicyWeights {
+            base: 1.0,
+            capture: 20.0,
+            escape: 10.0,
+            pattern: 5.0,
+            self_atari: 0.1,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Policy {
+    weights: PolicyWeights,
+    // Indexed by pattern code
+    patterns: Vec<bool>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::new(PolicyWeights::default())
+    }
+}
+
+// The allowed values of one pattern point
+fn values(c: char) -> Result<&'static [usize], String> {
+    match c {
+        '.' => Ok(&[0]),
+        'X' => Ok(&[1]),
+        'O' => Ok(&[2]),
+        '#' => Ok(&[3]),
+        'x' => Ok(&[0, 2, 3]),
+        'o' => Ok(&[0, 1, 3]),
+        '?' => Ok(&[0, 1, 2, 3]),
+        _ => Err(format!("Unknown pattern point '{}'", c)),
+    }
+}
+
+fn swap_colors(c: char) -> char {
+    match c {
+        'X' => 'O',
+        'O' => 'X',
+        'x' => 'o',
+        'o' => 'x',
+        c => c,
+    }
+}
+
+// Marks the code of every neighbourhood the points allow
+fn mark(table: &mut [bool], points: &[char], code: usize) -> Result<(), String> {
+    match points.split_first() {
+        None => table[code] = true,
+        Some((c, rest)) => {
+            for value in values(*c)? {
+                mark(table, rest, code << 2 | value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Policy {
+    pub fn new(weights: PolicyWeights) -> Self {
+        Policy::with_patterns(weights, &DEFAULT_PATTERNS).expect("the default patterns are valid")
+    }
+
+    pub fn with_patterns(weights: PolicyWeights, patterns: &[&str]) -> Result<Self, String> {
+        let mut table = vec![false; CODES];
+        for pattern in patterns {
+            let rows: Vec<Vec<char>> = pattern
+                .split(['|', '\n'])
+                .map(|row| {
+                    row.chars()
+                        .filter(|c| !c.is_whitespace())
+                        .collect::<Vec<char>>()
+                })
+                .filter(|row| !row.is_empty())
+                .collect();
+            if rows.len() != 3 || rows.iter().any(|row| row.len() != 3) {
+                return Err(format!("Pattern '{}' isn't 3x3", pattern));
+            }
+            if rows[1][1] != '.' {
+                return Err(format!("Pattern '{}' has no empty center", pattern));
+            }
+            for symmetry in Symmetry::ALL {
+                for swap in [false, true] {
+                    let points: Vec<char> = OFFSETS
+                        .iter()
+                        .map(|(row, col)| {
+                            let loc = Loc {
+                                row: (1 + row) as usize,
+                                col: (1 + col) as usize,
+                            };
+                            let loc = symmetry.apply(loc, (3, 3));
+                            let c = rows[loc.row][loc.col];
+                            if swap {
+                                swap_colors(c)
+                            } else {
+                                c
+                            }
+                        })
+                        .collect();
+                    mark(&mut table, &points, 0)?;
+                }
+            }
+        }
+        Ok(Policy {
+            weights,
+            patterns: table,
+        })
+    }
+
+    pub fn get_weights(&self) -> &PolicyWeights {
+        &self.weights
+    }
+
+    // Whether the neighbourhood of loc matches a pattern for the player to move
+    pub fn matches_pattern(&self, board: &Board, loc: Loc) -> bool {
+        if !loc.is_on_board(board.board_size()) {
+            return false;
+        }
+        let player = board.get_current_player().to_color();
+        let code = OFFSETS.iter().fold(0, |code, (row, col)| {
+            let nbr = Loc {
+                row: loc.row.wrapping_add_signed(*row),
+                col: loc.col.wrapping_add_signed(*col),
+            };
+            let value = match board.get(nbr) {
+                Color::Empty => 0,
+                Color::Invalid => 3,
+                color if color == player => 1,
+                _ => 2,
+            };
+            code << 2 | value
+        });
+        self.patterns[code]
+    }
+
+    // The weight of the move of the player to move, 0 when the point isn't empty or is an own eye.
+    // Doesn't check ko and suicide, gen_move skips those moves when it draws them
+    pub fn weight(&self, board: &Board, loc: Loc) -> f64 {
+        let player = board.get_current_player();
+        if !loc.is_on_board(board.board_size())
+            || board.get(loc) != Color::Empty
+            || board.is_own_eye(loc, player)
+        {
+            return 0.0;
+        }
+        let mut captures = false;
+        let mut in_atari = false;
+        let mut liberties: Vec<Loc> = vec![];
+        for nbr in loc.all_4nbr() {
+            match board.get(nbr) {
+                Color::Empty => liberties.push(nbr),
+                Color::Invalid => (),
+                color => {
+                    let group_liberties = board.get_liberties(nbr);
+                    if color != player.to_color() {
+                        captures |= group_liberties.len() == 1;
+                        continue;
+                    }
+                    in_atari |= group_liberties.len() == 1;
+                    liberties.extend(group_liberties.into_iter().filter(|lib| *lib != loc));
+                }
+            }
+        }
+        liberties.sort();
+        liberties.dedup();
+        let self_atari = !captures && liberties.len() < 2;
+
+        let mut weight = self.weights.base;
+        if captures {
+            weight += self.weights.capture;
+        }
+        if in_atari && !self_atari {
+            weight += self.weights.escape;
+        }
+        let near_last_move = board
+            .get_game_history()
+            .last()
+            .and_then(|last| last.get_loc())
+            .is_some_and(|last| last.row.abs_diff(loc.row) <= 1 && last.col.abs_diff(loc.col) <= 1);
+        if near_last_move && self.matches_pattern(board, loc) {
+            weight += self.weights.pattern;
+        }
+        if self_atari {
+            weight *= self.weights.self_atari;
+        }
+        weight
+    }
+
+    // A legal move drawn by weight, a pass when no move has any weight left
+    pub fn gen_move(&self, board: &Board, rng: &mut GameRng) -> MoveKind {
+        let player = board.get_current_player();
+        let (rows, cols) = board.board_size();
+        let mut moves: Vec<(Loc, f64)> = Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .map(|loc| (loc, self.weight(board, loc)))
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+        loop {
+            let total: f64 = moves.iter().map(|(_, weight)| weight).sum();
+            if moves.is_empty() || total <= 0.0 {
+                return MoveKind::Pass;
+            }
+            let mut left = rng.gen_range(0.0..total);
+            let index = moves
+                .iter()
+                .position(|(_, weight)| {
+                    left -= weight;
+                    left < 0.0
+                })
+                .unwrap_or(moves.len() - 1);
+            let (loc, _) = moves.swap_remove(index);
+            if board.move_is_valid(&Move::new(player, loc)) {
+                return MoveKind::Play(loc);
+            }
+        }
+    }
+
+    // Plays on from the position until two passes or max_moves more moves
+    pub fn playout(&self, board: &Board, max_moves: usize, rng: &mut GameRng) -> Board {
+        let mut board = board.without_history();
+        for _ in 0..max_moves {
+            if board.is_over() {
+                break;
+            }
+            let mv = Move {
+                player: board.get_current_player(),
+                kind: self.gen_move(&board, rng),
+            };
+            board.play(&mv);
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, Move, MoveKind, Player};
+    use crate::policy::{Policy, PolicyWeights};
+    use crate::seed::new_rng;
+
+    #[test]
+    fn captures_and_escapes_outweigh_quiet_moves() {
+        // White's stones at 1, 3 and 5, 1 are in atari, so is Black's stone at 4, 1 between them
+        let board = Board::from_diagram(
+            "Black to play
+             . O # . .
+             . . O . .
+             # . . . .
+             O . . . .
+             # . . . .",
+        )
+        .unwrap();
+        let policy = Policy::default();
+        let quiet = policy.weight(&board, Loc { row: 5, col: 5 });
+        assert_eq!(quiet, 1.0);
+        assert!(policy.weight(&board, Loc { row: 1, col: 4 }) > quiet);
+        assert!(policy.weight(&board, Loc { row: 4, col: 2 }) > quiet);
+
+        let greedy = Policy::new(PolicyWeights {
+            base: 0.0,
+            capture: 1.0,
+            ..PolicyWeights::default()
+        });
+        let mut rng = new_rng(5);
+        for _ in 0..10 {
+            let captures =
+                [(1, 4), (4, 2), (5, 2)].map(|(row, col)| MoveKind::Play(Loc { row, col }));
+            assert!(captures.contains(&greedy.gen_move(&board, &mut rng)));
+        }
+    }
+
+    #[test]
+    fn own_eyes_are_never_filled_and_self_ataris_are_rare() {
+        let board = Board::from_diagram(
+            "Black to play
+             . O . . .
+             O O . . #
+             . . . . .",
+        )
+        .unwrap();
+        let policy = Policy::default();
+        assert_eq!(policy.weight(&board, Loc { row: 1, col: 1 }), 0.0);
+        assert_eq!(policy.weight(&board, Loc { row: 1, col: 2 }), 0.0);
+        // Black in the corner would have a single liberty left
+        assert!(policy.weight(&board, Loc { row: 1, col: 5 }) < 1.0);
+        assert_eq!(policy.weight(&board, Loc { row: 1, col: 3 }), 1.0);
+    }
+
+    #[test]
+    fn patterns_match_around_the_last_move_in_every_orientation() {
+        assert!(Policy::with_patterns(PolicyWeights::default(), &["XO|...|???"]).is_err());
+        assert!(Policy::with_patterns(PolicyWeights::default(), &["XOX|.X.|???"]).is_err());
+        let policy = Policy::with_patterns(PolicyWeights::default(), &["XOX|...|???"]).unwrap();
+
+        // A hane below Black's stones at 3, 3 and 5, 3 is the pattern turned on its side
+        let mut board = Board::from_diagram(
+            ". . . . . .|. . . . . .|. . O . . .|. . . . . .|. . O . . .|. . . . . .",
+        )
+        .unwrap();
+        board.play(&Move::new(Player::White, Loc { row: 4, col: 3 }));
+        board.set_current_player(Player::Black);
+        assert!(policy.matches_pattern(&board, Loc { row: 4, col: 4 }));
+        assert!(policy.matches_pattern(&board, Loc { row: 4, col: 2 }));
+        assert!(!policy.matches_pattern(&board, Loc { row: 2, col: 2 }));
+        assert_eq!(policy.weight(&board, Loc { row: 4, col: 4 }), 6.0);
+
+        let played = Policy::default().playout(&Board::new(7, 7, 0.5), 500, &mut new_rng(1));
+        assert!(played.is_over());
+    }
+}
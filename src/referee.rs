@@ -0,0 +1,953 @@
+// Phantom Go referee.
+// Lingo:
+//     attempt  - a move a player tries to play, it becomes a move only if the referee accepts it
+//     rejected - an attempt on an opponent stone or an illegal point
+//     view     - what a single player knows about the board
+
+use crate::board::{is_eye, Board, Color, GameResult, Loc, Move, MoveKind, Player};
+use crate::knowledge::KnowledgeModel;
+use std::fmt;
+
+// A question to the referee instead of an attempt
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Probe {
+    // Is there a stone of either color on the point?
+    Occupied(Loc),
+    // Does the prober have a move that captures something?
+    AnyCapture,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProbeCost {
+    Free,
+    // The question uses up the turn
+    Pass,
+    // Given to the opponent at the end of the game
+    Points(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeRules {
+    pub occupied: ProbeCost,
+    pub any_capture: ProbeCost,
+}
+
+impl ProbeRules {
+    pub fn cost(&self, probe: Probe) -> ProbeCost {
+        match probe {
+            Probe::Occupied(_) => self.occupied,
+            Probe::AnyCapture => self.any_capture,
+        }
+    }
+}
+
+// Asking about captures is free like in Kriegspiel, looking at a point costs a point
+impl Default for ProbeRules {
+    fn default() -> Self {
+        ProbeRules {
+            occupied: ProbeCost::Points(1.0),
+            any_capture: ProbeCost::Free,
+        }
+    }
+}
+
+// Limits on rejected attempts, the default has none
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttemptRules {
+    // The referee passes for the player after this many rejected attempts in one turn
+    pub pass_after: Option<usize>,
+    // The game is lost after this many rejected attempts in total
+    pub forfeit_after: Option<usize>,
+    // The opponent hears where every rejected attempt was
+    pub reveal_to_opponent: bool,
+    // Given to the opponent for every rejected attempt
+    pub points_per_rejection: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    // Only the mover learns where the stone was played
+    Played(Loc),
+    // Tells the opponent that a stone was played, but not where
+    Moved,
+    Passed,
+    // Ends the game, announced to both players
+    Resigned,
+    // The point holds an opponent stone
+    Occupied(Loc),
+    // The point is off the board, taken by an own stone, suicidal or retakes a ko
+    Illegal(Loc),
+    // Stones of the given player removed from the board, announced to both players
+    Captured(Player, Vec<Loc>),
+    // The referee's yes or no to a probe
+    Answered(Probe, bool),
+    // Points the mover gives to the opponent for a probe or a rejected attempt
+    Penalized(f32),
+    // The opponent hears where the mover's attempt was rejected
+    Rejected(Loc),
+    // The mover lost the game for too many rejected attempts
+    Forfeited,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Announcement {
+    pub mover: Player,
+    pub event: Event,
+}
+
+impl Announcement {
+    pub fn is_for(&self, player: Player) -> bool {
+        match self.event {
+            Event::Played(_) | Event::Occupied(_) | Event::Illegal(_) => player == self.mover,
+            Event::Answered(_, _) => player == self.mover,
+            Event::Moved | Event::Rejected(_) => player != self.mover,
+            Event::Passed
+            | Event::Resigned
+            | Event::Forfeited
+            | Event::Captured(_, _)
+            | Event::Penalized(_) => true,
+        }
+    }
+
+    // The referee's message as the given player hears it
+    pub fn describe_for(&self, player: Player) -> String {
+        match &self.event {
+            Event::Played(loc) => format!("You played {}, {}", loc.row, loc.col),
+            Event::Moved => format!("{:?} has moved", self.mover),
+            Event::Passed if self.mover == player => String::from("You passed"),
+            Event::Passed => format!("{:?} passed", self.mover),
+            Event::Resigned if self.mover == player => String::from("You resigned"),
+            Event::Resigned => format!("{:?} resigned", self.mover),
+            Event::Occupied(loc) => format!("{}, {} is occupied, try again", loc.row, loc.col),
+            Event::Illegal(loc) => format!("{}, {} is illegal, try again", loc.row, loc.col),
+            Event::Captured(owner, stones) if *owner == player => {
+                format!(
+                    "You lost {} stone(s): {}",
+                    stones.len(),
+                    locs_to_string(stones)
+                )
+            }
+            Event::Captured(_, stones) => {
+                format!(
+                    "You captured {} stone(s): {}",
+                    stones.len(),
+                    locs_to_string(stones)
+                )
+            }
+            Event::Answered(probe, answer) => describe_answer(probe, *answer),
+            Event::Penalized(points) if self.mover == player => {
+                format!("You give {:?} {} point(s)", self.mover.opponent(), points)
+            }
+            Event::Penalized(points) => format!("{:?} gives you {} point(s)", self.mover, points),
+            Event::Rejected(loc) => format!(
+                "{:?} tried {}, {} and was rejected",
+                self.mover, loc.row, loc.col
+            ),
+            Event::Forfeited if self.mover == player => {
+                String::from("You lost the game for too many rejected attempts")
+            }
+            Event::Forfeited => format!(
+                "{:?} lost the game for too many rejected attempts",
+                self.mover
+            ),
+        }
+    }
+}
+
+fn describe_answer(probe: &Probe, answer: bool) -> String {
+    match (probe, answer) {
+        (Probe::Occupied(loc), true) => format!("{}, {} is occupied", loc.row, loc.col),
+        (Probe::Occupied(loc), false) => format!("{}, {} is empty", loc.row, loc.col),
+        (Probe::AnyCapture, true) => String::from("There is a capturing move"),
+        (Probe::AnyCapture, false) => String::from("There is no capturing move"),
+    }
+}
+
+fn locs_to_string(locs: &[Loc]) -> String {
+    let locs: Vec<String> = locs
+        .iter()
+        .map(|loc| format!("({}, {})", loc.row, loc.col))
+        .collect();
+    locs.join(" ")
+}
+
+// How an observer who sees everything describes the announcement
+impl fmt::Display for Announcement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.event {
+            Event::Played(loc) => write!(f, "{:?} played {}, {}", self.mover, loc.row, loc.col),
+            Event::Moved => write!(f, "{:?} has moved", self.mover),
+            Event::Passed => write!(f, "{:?} passed", self.mover),
+            Event::Resigned => write!(f, "{:?} resigned", self.mover),
+            Event::Occupied(loc) => write!(
+                f,
+                "{:?} tried {}, {}: occupied",
+                self.mover, loc.row, loc.col
+            ),
+            Event::Illegal(loc) => write!(
+                f,
+                "{:?} tried {}, {}: illegal",
+                self.mover, loc.row, loc.col
+            ),
+            Event::Captured(owner, stones) => write!(
+                f,
+                "{:?} captured {} {:?} stone(s): {}",
+                owner.opponent(),
+                stones.len(),
+                owner,
+                locs_to_string(stones)
+            ),
+            Event::Answered(probe, answer) => {
+                write!(
+                    f,
+                    "{:?} asked: {}",
+                    self.mover,
+                    describe_answer(probe, *answer)
+                )
+            }
+            Event::Penalized(points) => write!(f, "{:?} gives {} point(s)", self.mover, points),
+            Event::Rejected(loc) => write!(
+                f,
+                "{:?} hears of the attempt at {}, {}",
+                self.mover.opponent(),
+                loc.row,
+                loc.col
+            ),
+            Event::Forfeited => write!(
+                f,
+                "{:?} forfeits for too many rejected attempts",
+                self.mover
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerView {
+    player: Player,
+    // Own stones, discovered opponent stones and sentinels
+    fields: Vec<Vec<Color>>,
+    // Points the referee rejected as illegal during the current turn
+    tried: Vec<Loc>,
+    knowledge: KnowledgeModel,
+}
+
+impl PlayerView {
+    fn new(player: Player, board: &Board) -> Self {
+        let (rows, cols) = board.board_size();
+        let mut fields = vec![vec![Color::Invalid; cols]; rows];
+        for loc in Loc::get_all_on_board(rows, cols) {
+            if loc.is_on_board((rows, cols)) {
+                fields[loc.row][loc.col] = Color::Empty;
+            }
+        }
+        PlayerView {
+            player,
+            fields,
+            tried: vec![],
+            knowledge: KnowledgeModel::new(player, rows, cols),
+        }
+    }
+
+    pub fn get_player(&self) -> Player {
+        self.player
+    }
+
+    pub fn get(&self, loc: Loc) -> Color {
+        self.fields[loc.row][loc.col]
+    }
+
+    pub fn get_knowledge(&self) -> &KnowledgeModel {
+        &self.knowledge
+    }
+
+    fn set(&mut self, loc: Loc, color: Color) {
+        self.fields[loc.row][loc.col] = color;
+    }
+
+    pub fn board_size(&self) -> (usize, usize) {
+        (self.fields.len(), self.fields[0].len())
+    }
+
+    pub fn was_tried(&self, loc: Loc) -> bool {
+        self.tried.contains(&loc)
+    }
+
+    pub fn is_own_eye(&self, loc: Loc) -> bool {
+        is_eye(loc, self.player.to_color(), |loc| self.get(loc))
+    }
+
+    // Points that look empty, were not rejected this turn and are not own eyes
+    pub fn candidate_moves(&self) -> Vec<Loc> {
+        let (rows, cols) = self.board_size();
+        Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| self.get(*loc) == Color::Empty)
+            .filter(|loc| !self.was_tried(*loc) && !self.is_own_eye(*loc))
+            .collect()
+    }
+
+    fn update(&mut self, announcement: &Announcement) {
+        if !announcement.is_for(self.player) {
+            return;
+        }
+        self.knowledge.update(announcement);
+        match &announcement.event {
+            Event::Played(loc) => {
+                self.set(*loc, self.player.to_color());
+                self.tried.clear();
+            }
+            Event::Moved | Event::Resigned => (),
+            Event::Passed => {
+                if announcement.mover == self.player {
+                    self.tried.clear();
+                }
+            }
+            Event::Occupied(loc) => self.set(*loc, self.player.opponent().to_color()),
+            Event::Illegal(loc) => self.tried.push(*loc),
+            Event::Captured(_, stones) => {
+                for stone in stones {
+                    self.set(*stone, Color::Empty);
+                }
+            }
+            Event::Answered(Probe::Occupied(loc), true) => {
+                if self.get(*loc) != self.player.to_color() {
+                    self.set(*loc, self.player.opponent().to_color());
+                }
+            }
+            Event::Answered(..) | Event::Penalized(_) | Event::Rejected(_) | Event::Forfeited => {}
+        }
+    }
+}
+
+impl fmt::Display for PlayerView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.fields {
+            for field in row {
+                write!(f, "{} ", field)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RefereeSnapshot"))]
+pub struct Referee {
+    board: Board,
+    black_view: PlayerView,
+    white_view: PlayerView,
+    log: Vec<Announcement>,
+    // None when the game is played without probes
+    probe_rules: Option<ProbeRules>,
+    black_penalty: f32,
+    white_penalty: f32,
+    attempt_rules: AttemptRules,
+    rejected_this_turn: usize,
+    black_rejected: usize,
+    white_rejected: usize,
+    forfeited: Option<Player>,
+}
+
+// A deserialized referee before its views are checked against the board
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RefereeSnapshot {
+    board: Board,
+    black_view: PlayerView,
+    white_view: PlayerView,
+    log: Vec<Announcement>,
+    probe_rules: Option<ProbeRules>,
+    black_penalty: f32,
+    white_penalty: f32,
+    attempt_rules: AttemptRules,
+    rejected_this_turn: usize,
+    black_rejected: usize,
+    white_rejected: usize,
+    forfeited: Option<Player>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RefereeSnapshot> for Referee {
+    type Error = String;
+
+    fn try_from(snapshot: RefereeSnapshot) -> Result<Self, Self::Error> {
+        let referee = Referee {
+            board: snapshot.board,
+            black_view: snapshot.black_view,
+            white_view: snapshot.white_view,
+            log: snapshot.log,
+            probe_rules: snapshot.probe_rules,
+            black_penalty: snapshot.black_penalty,
+            white_penalty: snapshot.white_penalty,
+            attempt_rules: snapshot.attempt_rules,
+            rejected_this_turn: snapshot.rejected_this_turn,
+            black_rejected: snapshot.black_rejected,
+            white_rejected: snapshot.white_rejected,
+            forfeited: snapshot.forfeited,
+        };
+        referee.check()?;
+        Ok(referee)
+    }
+}
+
+impl Referee {
+    pub fn new(rows: usize, cols: usize, komi: f32) -> Self {
+        let board = Board::new(rows, cols, komi);
+        Referee {
+            black_view: PlayerView::new(Player::Black, &board),
+            white_view: PlayerView::new(Player::White, &board),
+            board,
+            log: vec![],
+            probe_rules: None,
+            black_penalty: 0.0,
+            white_penalty: 0.0,
+            attempt_rules: AttemptRules::default(),
+            rejected_this_turn: 0,
+            black_rejected: 0,
+            white_rejected: 0,
+            forfeited: None,
+        }
+    }
+
+    // The whole game as JSON, both views included, so it can be restored and played on
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> String {
+        serde_json::to_string(self).expect("A referee always serializes")
+    }
+
+    // Checked like any other deserialized referee
+    #[cfg(feature = "serde")]
+    pub fn restore(snapshot: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(snapshot)
+    }
+
+    // The board checks itself while it is deserialized, the views have to fit it
+    #[cfg(feature = "serde")]
+    fn check(&self) -> Result<(), String> {
+        let size = self.board.board_size();
+        for player in [Player::Black, Player::White] {
+            let view = self.get_view(player);
+            let fits = view.player == player
+                && view.fields.len() == size.0
+                && view.fields.iter().all(|row| row.len() == size.1)
+                && view.knowledge.fits(player, size);
+            if !fits {
+                return Err(format!("The view of {:?} doesn't fit the board", player));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_attempt_rules(&mut self, attempt_rules: AttemptRules) {
+        self.attempt_rules = attempt_rules;
+    }
+
+    pub fn get_attempt_rules(&self) -> AttemptRules {
+        self.attempt_rules
+    }
+
+    // Rejected attempts of the player over the whole game
+    pub fn get_rejected(&self, player: Player) -> usize {
+        match player {
+            Player::Black => self.black_rejected,
+            Player::White => self.white_rejected,
+        }
+    }
+
+    fn penalize(&mut self, player: Player, points: f32) {
+        match player {
+            Player::Black => self.black_penalty += points,
+            Player::White => self.white_penalty += points,
+        }
+    }
+
+    pub fn set_probe_rules(&mut self, probe_rules: Option<ProbeRules>) {
+        self.probe_rules = probe_rules;
+    }
+
+    pub fn get_probe_rules(&self) -> Option<ProbeRules> {
+        self.probe_rules
+    }
+
+    // The board score with probe penalties given to the other player
+    pub fn final_score(&self) -> GameResult {
+        if let Some(player) = self.forfeited {
+            return GameResult::Forfeit(player.opponent());
+        }
+        self.apply_penalties(&self.board.final_score())
+    }
+
+    // The final result after the players removed dead stones by hand, there is nothing to count
+    // when the game ended by forfeit or resignation
+    pub fn count_score(&self) -> GameResult {
+        if self.forfeited.is_some() || self.board.resigned().is_some() {
+            return self.final_score();
+        }
+        self.apply_penalties(&self.board.clone().count_score())
+    }
+
+    fn apply_penalties(&self, result: &GameResult) -> GameResult {
+        match result.black_margin() {
+            Some(margin) => {
+                GameResult::from_black_margin(margin - self.black_penalty + self.white_penalty)
+            }
+            None => result.clone(),
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_current_player(&self) -> Player {
+        self.board.get_current_player()
+    }
+
+    pub fn get_view(&self, player: Player) -> &PlayerView {
+        match player {
+            Player::Black => &self.black_view,
+            Player::White => &self.white_view,
+        }
+    }
+
+    pub fn get_log(&self) -> &Vec<Announcement> {
+        &self.log
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.board.is_over() || self.forfeited.is_some()
+    }
+
+    pub fn pass(&mut self) -> Vec<Announcement> {
+        self.end_turn(MoveKind::Pass, Event::Passed)
+    }
+
+    pub fn resign(&mut self) -> Vec<Announcement> {
+        self.end_turn(MoveKind::Resign, Event::Resigned)
+    }
+
+    fn end_turn(&mut self, kind: MoveKind, event: Event) -> Vec<Announcement> {
+        if self.is_over() {
+            return vec![];
+        }
+        let mover = self.get_current_player();
+        self.board.play(&Move {
+            player: mover,
+            kind,
+        });
+        self.rejected_this_turn = 0;
+        self.announce(mover, vec![event])
+    }
+
+    // Judges an attempt of the current player and returns what was announced because of it.
+    // Only points are attempted, passes and resignations end the turn through pass and resign
+    pub fn attempt(&mut self, loc: Loc) -> Vec<Announcement> {
+        if self.is_over() {
+            return vec![];
+        }
+        let mover = self.get_current_player();
+        let mv = Move::new(mover, loc);
+        let opponent = mover.opponent().to_color();
+
+        let mut events = vec![];
+        if !loc.is_on_board(self.board.board_size()) {
+            events.push(Event::Illegal(loc));
+        } else if self.board.get(loc) == opponent {
+            events.push(Event::Occupied(loc));
+        } else if !self.board.move_is_valid(&mv) {
+            events.push(Event::Illegal(loc));
+        } else {
+            let (rows, cols) = self.board.board_size();
+            let opponent_stones: Vec<Loc> = Loc::get_all_on_board(rows, cols)
+                .into_iter()
+                .filter(|stone| self.board.get(*stone) == opponent)
+                .collect();
+            self.board.play(&mv);
+            let captured: Vec<Loc> = opponent_stones
+                .into_iter()
+                .filter(|stone| self.board.get(*stone) == Color::Empty)
+                .collect();
+
+            events.push(Event::Played(loc));
+            events.push(Event::Moved);
+            if !captured.is_empty() {
+                events.push(Event::Captured(mover.opponent(), captured));
+            }
+        }
+
+        match events[..] {
+            [Event::Occupied(loc)] | [Event::Illegal(loc)] => {
+                let mut announcements = self.announce(mover, events);
+                announcements.extend(self.reject(mover, loc));
+                announcements
+            }
+            _ => {
+                self.rejected_this_turn = 0;
+                self.announce(mover, events)
+            }
+        }
+    }
+
+    // Applies the attempt rules after a rejected attempt
+    fn reject(&mut self, mover: Player, loc: Loc) -> Vec<Announcement> {
+        let rules = self.attempt_rules;
+        self.rejected_this_turn += 1;
+        match mover {
+            Player::Black => self.black_rejected += 1,
+            Player::White => self.white_rejected += 1,
+        }
+
+        let mut events = vec![];
+        if rules.reveal_to_opponent {
+            events.push(Event::Rejected(loc));
+        }
+        if rules.points_per_rejection > 0.0 {
+            self.penalize(mover, rules.points_per_rejection);
+            events.push(Event::Penalized(rules.points_per_rejection));
+        }
+        let forfeit = rules
+            .forfeit_after
+            .is_some_and(|limit| self.get_rejected(mover) >= limit);
+        if forfeit {
+            self.forfeited = Some(mover);
+            events.push(Event::Forfeited);
+        }
+
+        let mut announcements = self.announce(mover, events);
+        let pass = rules
+            .pass_after
+            .is_some_and(|limit| self.rejected_this_turn >= limit);
+        if pass && !forfeit {
+            announcements.extend(self.pass());
+        }
+        announcements
+    }
+
+    fn announce(&mut self, mover: Player, events: Vec<Event>) -> Vec<Announcement> {
+        let announcements: Vec<Announcement> = events
+            .into_iter()
+            .map(|event| Announcement { mover, event })
+            .collect();
+        for announcement in &announcements {
+            self.black_view.update(announcement);
+            self.white_view.update(announcement);
+            self.log.push(announcement.clone());
+        }
+        announcements
+    }
+
+    fn has_capturing_move(&self, player: Player) -> bool {
+        let (rows, cols) = self.board.board_size();
+        Loc::get_all_on_board(rows, cols).into_iter().any(|loc| {
+            let mv = Move::new(player, loc);
+            if self.board.get(loc) != Color::Empty || !self.board.move_is_valid(&mv) {
+                return false;
+            }
+            let mut board = self.board.clone();
+            board.play(&mv);
+            board.get_captures(player) > self.board.get_captures(player)
+        })
+    }
+
+    // Answers a question of the current player and charges its cost, does nothing when probes are not allowed
+    pub fn probe(&mut self, probe: Probe) -> Vec<Announcement> {
+        let rules = match self.probe_rules {
+            Some(rules) if !self.is_over() => rules,
+            _ => return vec![],
+        };
+        let mover = self.get_current_player();
+        let answer = match probe {
+            Probe::Occupied(loc) => {
+                loc.is_on_board(self.board.board_size())
+                    && matches!(self.board.get(loc), Color::Black | Color::White)
+            }
+            Probe::AnyCapture => self.has_capturing_move(mover),
+        };
+
+        let mut announcements = self.announce(mover, vec![Event::Answered(probe, answer)]);
+        match rules.cost(probe) {
+            ProbeCost::Free => (),
+            ProbeCost::Pass => announcements.extend(self.pass()),
+            ProbeCost::Points(points) => {
+                self.penalize(mover, points);
+                announcements.extend(self.announce(mover, vec![Event::Penalized(points)]));
+            }
+        }
+        announcements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Color;
+    use crate::board::GameResult;
+    use crate::board::Loc;
+    use crate::board::Player;
+    use crate::referee::AttemptRules;
+    use crate::referee::Event;
+    use crate::referee::Probe;
+    use crate::referee::ProbeCost;
+    use crate::referee::ProbeRules;
+    use crate::referee::Referee;
+
+    #[test]
+    fn players_see_only_their_own_stones() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.attempt(Loc { row: 2, col: 2 });
+        referee.attempt(Loc { row: 3, col: 3 });
+
+        let black_view = referee.get_view(Player::Black);
+        let white_view = referee.get_view(Player::White);
+        assert_eq!(black_view.get(Loc { row: 2, col: 2 }), Color::Black);
+        assert_eq!(black_view.get(Loc { row: 3, col: 3 }), Color::Empty);
+        assert_eq!(white_view.get(Loc { row: 2, col: 2 }), Color::Empty);
+        assert_eq!(white_view.get(Loc { row: 3, col: 3 }), Color::White);
+    }
+
+    #[test]
+    fn attempt_on_opponent_stone_reveals_it_and_keeps_the_turn() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.attempt(Loc { row: 2, col: 2 });
+
+        let announcements = referee.attempt(Loc { row: 2, col: 2 });
+        assert_eq!(
+            announcements[0].event,
+            Event::Occupied(Loc { row: 2, col: 2 })
+        );
+        assert!(!announcements[0].is_for(Player::Black));
+        assert_eq!(referee.get_current_player(), Player::White);
+        assert_eq!(
+            referee.get_view(Player::White).get(Loc { row: 2, col: 2 }),
+            Color::Black
+        );
+    }
+
+    #[test]
+    fn captures_are_announced_to_both_players() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        let attempts = [
+            Loc { row: 1, col: 1 },
+            Loc { row: 1, col: 2 },
+            Loc { row: 5, col: 5 },
+            Loc { row: 2, col: 1 },
+        ];
+        for loc in attempts {
+            referee.attempt(loc);
+        }
+
+        let last = referee.get_log().last().unwrap();
+        assert_eq!(
+            last.event,
+            Event::Captured(Player::Black, vec![Loc { row: 1, col: 1 }])
+        );
+        assert!(last.is_for(Player::Black) && last.is_for(Player::White));
+        assert_eq!(
+            last.describe_for(Player::White),
+            "You captured 1 stone(s): (1, 1)"
+        );
+        assert_eq!(
+            last.describe_for(Player::Black),
+            "You lost 1 stone(s): (1, 1)"
+        );
+        assert_eq!(
+            referee.get_view(Player::Black).get(Loc { row: 1, col: 1 }),
+            Color::Empty
+        );
+    }
+
+    #[test]
+    fn probes_are_answered_and_charged() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        assert!(referee.probe(Probe::AnyCapture).is_empty());
+
+        referee.set_probe_rules(Some(ProbeRules {
+            occupied: ProbeCost::Points(2.0),
+            any_capture: ProbeCost::Pass,
+        }));
+        referee.attempt(Loc { row: 1, col: 1 });
+        referee.attempt(Loc { row: 1, col: 2 });
+
+        let announcements = referee.probe(Probe::AnyCapture);
+        assert_eq!(
+            announcements[0].event,
+            Event::Answered(Probe::AnyCapture, false)
+        );
+        assert_eq!(announcements[1].event, Event::Passed);
+        assert_eq!(referee.get_current_player(), Player::White);
+
+        let announcements = referee.probe(Probe::Occupied(Loc { row: 1, col: 1 }));
+        assert_eq!(
+            announcements[0].event,
+            Event::Answered(Probe::Occupied(Loc { row: 1, col: 1 }), true)
+        );
+        assert!(!announcements[0].is_for(Player::Black));
+        assert_eq!(announcements[1].event, Event::Penalized(2.0));
+        assert_eq!(
+            referee.get_view(Player::White).get(Loc { row: 1, col: 1 }),
+            Color::Black
+        );
+        assert_eq!(referee.get_current_player(), Player::White);
+
+        // White can capture at 2, 1
+        let announcements = referee.probe(Probe::AnyCapture);
+        assert_eq!(
+            announcements[0].event,
+            Event::Answered(Probe::AnyCapture, true)
+        );
+
+        // Komi 0.5 and 2 points from White's question
+        assert_eq!(
+            referee.final_score(),
+            GameResult::Player(Player::Black, 1.5)
+        );
+    }
+
+    #[test]
+    fn resigning_ends_the_game_for_both_players() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.attempt(Loc { row: 3, col: 3 });
+        let announcements = referee.resign();
+        assert_eq!(announcements[0].event, Event::Resigned);
+        assert!(announcements[0].is_for(Player::Black));
+        assert_eq!(
+            announcements[0].describe_for(Player::Black),
+            "White resigned"
+        );
+        assert!(referee.is_over());
+        assert_eq!(
+            referee.final_score(),
+            GameResult::Resignation(Player::Black)
+        );
+    }
+
+    #[test]
+    fn typed_sentinel_locations_neither_resign_nor_pass() {
+        let far = Loc { row: 98, col: 98 };
+        let farther = Loc { row: 99, col: 99 };
+        assert_eq!(Loc::from_string("98, 98"), Some(far));
+        assert_eq!(Loc::from_string("99,99"), Some(farther));
+
+        let mut referee = Referee::new(7, 7, 0.5);
+        let announcements = referee.attempt(far);
+        assert_eq!(announcements[0].event, Event::Illegal(far));
+        let announcements = referee.attempt(farther);
+        assert_eq!(announcements[0].event, Event::Illegal(farther));
+        assert!(!referee.is_over());
+        assert_eq!(referee.get_current_player(), Player::Black);
+        assert!(referee.get_board().get_game_history().is_empty());
+
+        // On a large board they are points like any other
+        let mut referee = Referee::new(120, 120, 0.5);
+        let announcements = referee.attempt(far);
+        assert_eq!(announcements[0].event, Event::Played(far));
+        let announcements = referee.attempt(farther);
+        assert_eq!(announcements[0].event, Event::Played(farther));
+        assert!(!referee.is_over());
+        assert_eq!(referee.get_board().get(far), Color::Black);
+        assert_eq!(referee.get_board().get(farther), Color::White);
+        assert_eq!(referee.get_current_player(), Player::Black);
+    }
+
+    #[test]
+    fn rejected_attempts_pass_the_turn_reveal_the_point_and_cost_points() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.set_attempt_rules(AttemptRules {
+            pass_after: Some(2),
+            reveal_to_opponent: true,
+            points_per_rejection: 1.0,
+            ..AttemptRules::default()
+        });
+        referee.attempt(Loc { row: 3, col: 3 });
+
+        let announcements = referee.attempt(Loc { row: 3, col: 3 });
+        assert_eq!(
+            announcements[1].event,
+            Event::Rejected(Loc { row: 3, col: 3 })
+        );
+        assert!(announcements[1].is_for(Player::Black));
+        assert!(!announcements[1].is_for(Player::White));
+        assert_eq!(announcements[2].event, Event::Penalized(1.0));
+        assert_eq!(referee.get_current_player(), Player::White);
+
+        let announcements = referee.attempt(Loc { row: 0, col: 0 });
+        assert_eq!(announcements.last().unwrap().event, Event::Passed);
+        assert_eq!(referee.get_current_player(), Player::Black);
+        assert_eq!(referee.get_rejected(Player::White), 2);
+
+        // The turn count starts over, the total doesn't
+        referee.attempt(Loc { row: 3, col: 3 });
+        assert_eq!(referee.get_current_player(), Player::Black);
+    }
+
+    #[test]
+    fn too_many_rejected_attempts_lose_the_game() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.set_attempt_rules(AttemptRules {
+            pass_after: Some(2),
+            forfeit_after: Some(3),
+            ..AttemptRules::default()
+        });
+        referee.attempt(Loc { row: 3, col: 3 });
+        referee.attempt(Loc { row: 3, col: 3 });
+        referee.attempt(Loc { row: 3, col: 3 });
+        referee.attempt(Loc { row: 2, col: 2 });
+
+        let announcements = referee.attempt(Loc { row: 3, col: 3 });
+        assert_eq!(announcements[1].event, Event::Forfeited);
+        assert!(referee.is_over());
+        assert!(referee.attempt(Loc { row: 2, col: 2 }).is_empty());
+        assert_eq!(referee.final_score(), GameResult::Forfeit(Player::Black));
+        // Nothing is left to count after a forfeit
+        assert_eq!(referee.count_score(), GameResult::Forfeit(Player::Black));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_snapshot_restores_the_game_with_both_views() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.set_probe_rules(Some(ProbeRules::default()));
+        referee.attempt(Loc { row: 3, col: 3 });
+        referee.attempt(Loc { row: 3, col: 3 });
+        referee.probe(Probe::Occupied(Loc { row: 2, col: 2 }));
+
+        let mut restored = Referee::restore(&referee.snapshot()).unwrap();
+        assert_eq!(restored.snapshot(), referee.snapshot());
+        assert!(restored.get_board() == referee.get_board());
+        assert_eq!(
+            restored.get_view(Player::White),
+            referee.get_view(Player::White)
+        );
+
+        restored.attempt(Loc { row: 2, col: 2 });
+        assert_eq!(restored.get_current_player(), Player::Black);
+        assert!(Referee::restore("{}").is_err());
+
+        let mut tampered: serde_json::Value = serde_json::from_str(&referee.snapshot()).unwrap();
+        tampered["white_view"]["fields"]
+            .as_array_mut()
+            .unwrap()
+            .pop();
+        assert!(Referee::restore(&tampered.to_string()).is_err());
+        // Deserializing without restore checks the views just the same
+        assert!(serde_json::from_value::<Referee>(tampered).is_err());
+    }
+
+    #[test]
+    fn two_passes_end_the_game() {
+        let mut referee = Referee::new(7, 7, 0.5);
+        referee.pass();
+        assert!(!referee.is_over());
+        referee.pass();
+        assert!(referee.is_over());
+    }
+}
@@ -0,0 +1,31 @@
+// Every source of randomness is seeded from here, so a game can be replayed from (seed, config).
+// ChaCha is used instead of StdRng because its output doesn't change between rand versions.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+pub type GameRng = ChaCha8Rng;
+
+pub fn new_rng(seed: u64) -> GameRng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+// Independent seed for the given stream (an agent, a game of a tournament, ...), splitmix64 mixing
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::seed::derive_seed;
+
+    #[test]
+    fn derived_seeds_differ_between_streams_and_repeat_for_the_same_stream() {
+        assert_eq!(derive_seed(42, 0), derive_seed(42, 0));
+        assert_ne!(derive_seed(42, 0), derive_seed(42, 1));
+        assert_ne!(derive_seed(42, 0), derive_seed(43, 0));
+    }
+}
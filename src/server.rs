@@ -0,0 +1,422 @@
+// Phantom games in the browser, one game per server, each WebSocket connection sits in one seat.
+// Lingo:
+//     seat    - the player a connection plays for, a seat holds at most one connection
+//     command - a text message from the browser: "play r, c", "pass", "resign" or "new"
+//     state   - a JSON message to the browser with the player's view and referee messages
+
+use crate::board::{Color, Loc, Player};
+use crate::referee::Referee;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+const INDEX_HTML: &str = include_str!("server.html");
+// How often a connection looks for moves of the other player
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const ROWS: usize = 11;
+const COLS: usize = 11;
+const KOMI: f32 = 7.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Play(Loc),
+    Pass,
+    Resign,
+    // Starts over once the game is over
+    New,
+}
+
+impl Command {
+    // Points to play are checked against the board, anything else is for the referee to judge
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let loc = match text.trim() {
+            "pass" => return Ok(Command::Pass),
+            "resign" => return Ok(Command::Resign),
+            "new" => return Ok(Command::New),
+            text => text.strip_prefix("play ").and_then(Loc::from_string),
+        };
+        match loc {
+            Some(loc) if loc.is_on_board((ROWS, COLS)) => Ok(Command::Play(loc)),
+            Some(loc) => Err(format!("{}, {} is not on the board", loc.row, loc.col)),
+            None => Err(format!("Unknown command: {}", text)),
+        }
+    }
+}
+
+pub struct Lobby {
+    referee: Referee,
+    seats: Vec<Player>,
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Lobby {
+            referee: Referee::new(ROWS, COLS, KOMI),
+            seats: vec![],
+        }
+    }
+
+    // The wanted seat, or any free one when there is no wish
+    fn take_seat(&mut self, wish: Option<Player>) -> Option<Player> {
+        let player = match wish {
+            Some(player) => player,
+            None if !self.seats.contains(&Player::Black) => Player::Black,
+            None => Player::White,
+        };
+        if self.seats.contains(&player) {
+            return None;
+        }
+        self.seats.push(player);
+        Some(player)
+    }
+
+    fn leave_seat(&mut self, player: Player) {
+        self.seats.retain(|seat| *seat != player);
+    }
+
+    // None when the command was carried out, otherwise why it wasn't
+    fn apply(&mut self, player: Player, command: Command) -> Option<String> {
+        if command == Command::New {
+            if !self.referee.is_over() {
+                return Some(String::from("The game isn't over yet"));
+            }
+            self.referee = Referee::new(ROWS, COLS, KOMI);
+            return None;
+        }
+        if self.referee.is_over() {
+            return Some(String::from("The game is over"));
+        }
+        if self.referee.get_current_player() != player {
+            return Some(String::from("It's not your turn"));
+        }
+        match command {
+            Command::Play(loc) => self.referee.attempt(loc),
+            Command::Pass => self.referee.pass(),
+            _ => self.referee.resign(),
+        };
+        None
+    }
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Lobby::new()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            c if c.is_control() => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json + "\""
+}
+
+fn json_list(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|item| json_string(item)).collect();
+    format!("[{}]", items.join(","))
+}
+
+// Only what the player may know, the true board is shown once the game is over
+pub fn state_json(referee: &Referee, player: Player, error: Option<&str>) -> String {
+    let view = referee.get_view(player);
+    let (rows, cols) = referee.get_board().board_size();
+    let mut board = vec![];
+    for row in 1..rows - 1 {
+        let mut line = String::new();
+        for col in 1..cols - 1 {
+            let loc = Loc { row, col };
+            let color = if referee.is_over() {
+                referee.get_board().get(loc)
+            } else {
+                view.get(loc)
+            };
+            line.push(match color {
+                Color::Black => 'B',
+                Color::White => 'W',
+                _ => '.',
+            });
+        }
+        board.push(line);
+    }
+    let messages: Vec<String> = referee
+        .get_log()
+        .iter()
+        .filter(|announcement| announcement.is_for(player))
+        .map(|announcement| announcement.describe_for(player))
+        .collect();
+    let result = match referee.is_over() {
+        true => json_string(&referee.final_score().to_string()),
+        false => String::from("null"),
+    };
+    let error = match error {
+        Some(error) => json_string(error),
+        None => String::from("null"),
+    };
+
+    format!(
+        "{{\"player\":{},\"turn\":{},\"over\":{},\"result\":{},\"board\":{},\"messages\":{},\"error\":{}}}",
+        json_string(&format!("{:?}", player)),
+        json_string(&format!("{:?}", referee.get_current_player())),
+        referee.is_over(),
+        result,
+        json_list(&board),
+        json_list(&messages),
+        error
+    )
+}
+
+fn wished_player(path: &str) -> Option<Player> {
+    let query = path.split_once('?')?.1;
+    query.split('&').find_map(|pair| match pair {
+        "player=black" => Some(Player::Black),
+        "player=white" => Some(Player::White),
+        _ => None,
+    })
+}
+
+// Serves until the listener fails, every connection gets its own thread
+pub fn serve(listener: TcpListener) -> io::Result<()> {
+    let lobby = Arc::new(Mutex::new(Lobby::new()));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(stream, &lobby) {
+                eprintln!("Connection failed: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, lobby: &Mutex<Lobby>) -> io::Result<()> {
+    // Peeking leaves the request in the stream for the WebSocket handshake
+    let mut head = [0; 2048];
+    let length = stream.peek(&mut head)?;
+    let head = String::from_utf8_lossy(&head[..length]).to_lowercase();
+    let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    if head.contains("upgrade: websocket") {
+        return play(stream, lobby, wished_player(&path));
+    }
+
+    // The request is read only to be answered, its body doesn't matter
+    let mut request = [0; 2048];
+    let _ = stream.read(&mut request)?;
+    let response = if path == "/" || path == "/index.html" {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            INDEX_HTML.len(),
+            INDEX_HTML
+        )
+    } else {
+        String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+    };
+    stream.write_all(response.as_bytes())
+}
+
+// A connection that panicked while holding the lobby leaves it poisoned, the others play on with it
+fn lock(lobby: &Mutex<Lobby>) -> MutexGuard<'_, Lobby> {
+    lobby.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, text: String) -> io::Result<()> {
+    socket.send(Message::text(text)).map_err(io::Error::other)
+}
+
+fn play(stream: TcpStream, lobby: &Mutex<Lobby>, wish: Option<Player>) -> io::Result<()> {
+    // The seat is taken before the handshake, so two clients can't both get it
+    let seat = lock(lobby).take_seat(wish);
+    let mut socket = match tungstenite::accept(stream) {
+        Ok(socket) => socket,
+        Err(error) => {
+            if let Some(player) = seat {
+                lock(lobby).leave_seat(player);
+            }
+            return Err(io::Error::other(error));
+        }
+    };
+    let player = match seat {
+        Some(player) => player,
+        None => {
+            send(
+                &mut socket,
+                String::from("{\"error\":\"The seat is taken\"}"),
+            )?;
+            return socket.close(None).map_err(io::Error::other);
+        }
+    };
+
+    let outcome = socket
+        .get_mut()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .and_then(|_| play_seated(&mut socket, lobby, player));
+    lock(lobby).leave_seat(player);
+    outcome
+}
+
+fn play_seated(
+    socket: &mut WebSocket<TcpStream>,
+    lobby: &Mutex<Lobby>,
+    player: Player,
+) -> io::Result<()> {
+    // The log length and the game the last state was sent for, a new game starts with an empty log
+    let mut sent: Option<(usize, bool)> = None;
+    let mut error = None;
+    loop {
+        let state = {
+            let lobby = lock(lobby);
+            let seen = (lobby.referee.get_log().len(), lobby.referee.is_over());
+            if sent == Some(seen) && error.is_none() {
+                None
+            } else {
+                sent = Some(seen);
+                Some(state_json(&lobby.referee, player, error.take().as_deref()))
+            }
+        };
+        if let Some(state) = state {
+            send(socket, state)?;
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                error = match Command::parse(&text) {
+                    Ok(command) => lock(lobby).apply(player, command),
+                    Err(error) => Some(error),
+                };
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return Ok(())
+            }
+            Err(error) => return Err(io::Error::other(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Loc, Player};
+    use crate::referee::Referee;
+    use crate::server::{lock, serve, state_json, Command, Lobby};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use tungstenite::{connect, Message};
+
+    #[test]
+    fn commands_are_parsed() {
+        assert_eq!(
+            Command::parse("play 3, 4"),
+            Ok(Command::Play(Loc { row: 3, col: 4 }))
+        );
+        assert_eq!(Command::parse(" pass\n"), Ok(Command::Pass));
+        assert!(Command::parse("3, 4").is_err());
+        // Points off the board are rejected before they reach the referee
+        for text in ["play 98, 98", "play 99, 99", "play 0, 3", "play 10, 10"] {
+            assert!(Command::parse(text).is_err());
+        }
+    }
+
+    #[test]
+    fn a_connection_panicking_in_the_lobby_leaves_it_usable() {
+        let lobby = Arc::new(Mutex::new(Lobby::new()));
+        let held = Arc::clone(&lobby);
+        let _ = thread::spawn(move || {
+            let _lobby = held.lock().unwrap();
+            panic!("the connection broke down");
+        })
+        .join();
+        assert!(lobby.is_poisoned());
+        assert_eq!(lock(&lobby).take_seat(None), Some(Player::Black));
+    }
+
+    #[test]
+    fn state_shows_only_the_players_view() {
+        let mut referee = Referee::new(4, 4, 0.5);
+        referee.attempt(Loc { row: 1, col: 1 });
+        assert_eq!(
+            state_json(&referee, Player::White, Some("It's \"not\" your turn")),
+            "{\"player\":\"White\",\"turn\":\"White\",\"over\":false,\"result\":null,\
+             \"board\":[\"..\",\"..\"],\"messages\":[\"Black has moved\"],\
+             \"error\":\"It's \\\"not\\\" your turn\"}"
+        );
+        assert!(state_json(&referee, Player::Black, None).contains("[\"B.\",\"..\"]"));
+    }
+
+    #[test]
+    fn two_local_clients_play_a_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener));
+
+        let mut page = String::new();
+        let mut http = TcpStream::connect(address).unwrap();
+        http.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        http.read_to_string(&mut page).unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+
+        let (mut black, _) = connect(format!("ws://{}/ws?player=black", address)).unwrap();
+        let (mut white, _) = connect(format!("ws://{}/ws", address)).unwrap();
+        let (mut intruder, _) = connect(format!("ws://{}/ws?player=black", address)).unwrap();
+        assert!(intruder
+            .read()
+            .unwrap()
+            .to_text()
+            .unwrap()
+            .contains("The seat is taken"));
+
+        assert!(black
+            .read()
+            .unwrap()
+            .to_text()
+            .unwrap()
+            .contains("\"player\":\"Black\""));
+        assert!(white
+            .read()
+            .unwrap()
+            .to_text()
+            .unwrap()
+            .contains("\"player\":\"White\""));
+        black.send(Message::text("play 5, 5")).unwrap();
+        assert!(black
+            .read()
+            .unwrap()
+            .to_text()
+            .unwrap()
+            .contains("You played 5, 5"));
+        let state = white.read().unwrap().into_text().unwrap();
+        assert!(state.contains("Black has moved"));
+        let board = state
+            .split("\"board\":")
+            .nth(1)
+            .unwrap()
+            .split(']')
+            .next()
+            .unwrap();
+        assert!(!board.contains('B'));
+
+        white.send(Message::text("resign")).unwrap();
+        assert!(black
+            .read()
+            .unwrap()
+            .to_text()
+            .unwrap()
+            .contains("\"result\":\"Black wins by resignation\""));
+    }
+}
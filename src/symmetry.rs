@@ -0,0 +1,265 @@
+// The 8 symmetries of the board, rotations and reflections, and position hashes that don't change under them.
+// Lingo:
+//     symmetry       - one of the 8 dihedral transforms, a square board maps onto itself under all of them
+//     transposing    - a symmetry that swaps rows and columns, a 9x13 board becomes a 13x9 board
+//     position hash  - a hash of the stones and the player to move, the same on every run
+//     canonical hash - the smallest position hash over all 8 symmetries of the position
+// Sizes include sentinels like everywhere else, sentinels map onto sentinels.
+
+use crate::board::{Board, Color, Loc, Move, MoveKind, Player};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    // Clockwise
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // Top and bottom change places
+    FlipRows,
+    // Left and right change places
+    FlipCols,
+    // The main diagonal from the top left stays in place
+    Transpose,
+    // The other diagonal stays in place
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipRows,
+        Symmetry::FlipCols,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            symmetry => symmetry,
+        }
+    }
+
+    pub fn is_transposing(self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::Transpose
+                | Symmetry::AntiTranspose
+        )
+    }
+
+    // The size of the transformed board
+    pub fn board_size(self, board_size: (usize, usize)) -> (usize, usize) {
+        let (rows, cols) = board_size;
+        if self.is_transposing() {
+            (cols, rows)
+        } else {
+            (rows, cols)
+        }
+    }
+
+    pub fn apply(self, loc: Loc, board_size: (usize, usize)) -> Loc {
+        let (last_row, last_col) = (board_size.0 - 1, board_size.1 - 1);
+        let (row, col) = (loc.row, loc.col);
+        let (row, col) = match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, last_row - row),
+            Symmetry::Rotate180 => (last_row - row, last_col - col),
+            Symmetry::Rotate270 => (last_col - col, row),
+            Symmetry::FlipRows => (last_row - row, col),
+            Symmetry::FlipCols => (row, last_col - col),
+            Symmetry::Transpose => (col, row),
+            Symmetry::AntiTranspose => (last_col - col, last_row - row),
+        };
+        Loc { row, col }
+    }
+
+    pub fn apply_move(self, mv: &Move, board_size: (usize, usize)) -> Move {
+        let kind = match mv.kind {
+            MoveKind::Play(loc) => MoveKind::Play(self.apply(loc, board_size)),
+            kind => kind,
+        };
+        Move {
+            player: mv.player,
+            kind,
+        }
+    }
+}
+
+// FNV-1a, fixed so hashes can be stored in files and compared between runs
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+fn fnv(hash: u64, value: u64) -> u64 {
+    (hash ^ value).wrapping_mul(FNV_PRIME)
+}
+
+// Hash of the position the symmetry turns the board into, komi, captures and ko don't count
+pub fn position_hash(board: &Board, symmetry: Symmetry) -> u64 {
+    let size = symmetry.board_size(board.board_size());
+    let back = symmetry.inverse();
+    let player = match board.get_current_player() {
+        Player::Black => 1,
+        Player::White => 2,
+    };
+    let mut hash = fnv(fnv(fnv(FNV_OFFSET, size.0 as u64), size.1 as u64), player);
+    for row in 1..size.0 - 1 {
+        for col in 1..size.1 - 1 {
+            let color = match board.get(back.apply(Loc { row, col }, size)) {
+                Color::Black => 1,
+                Color::White => 2,
+                _ => 0,
+            };
+            hash = fnv(hash, color);
+        }
+    }
+    hash
+}
+
+// The same for every symmetric copy of the position, with the symmetry that gives the canonical copy,
+// moves looked up for the canonical copy map back with its inverse
+pub fn canonical_hash(board: &Board) -> (u64, Symmetry) {
+    Symmetry::ALL
+        .iter()
+        .map(|symmetry| (position_hash(board, *symmetry), *symmetry))
+        .min_by_key(|(hash, _)| *hash)
+        .unwrap()
+}
+
+// The symmetries that map the position onto itself, all 8 on an empty square board
+pub fn symmetries_of(board: &Board) -> Vec<Symmetry> {
+    let size = board.board_size();
+    let (rows, cols) = size;
+    Symmetry::ALL
+        .into_iter()
+        .filter(|symmetry| symmetry.board_size(size) == size)
+        .filter(|symmetry| {
+            Loc::get_all_on_board(rows, cols)
+                .into_iter()
+                .all(|loc| board.get(symmetry.apply(loc, size)) == board.get(loc))
+        })
+        .collect()
+}
+
+// One move of every set of moves that lead to symmetric positions, the first of each set in the given order
+pub fn unique_moves(board: &Board, moves: &[Loc]) -> Vec<Loc> {
+    let size = board.board_size();
+    let symmetries = symmetries_of(board);
+    let mut unique: Vec<Loc> = vec![];
+    for loc in moves {
+        let seen = symmetries
+            .iter()
+            .any(|symmetry| unique.contains(&symmetry.apply(*loc, size)));
+        if !seen {
+            unique.push(*loc);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, Move, Player};
+    use crate::symmetry::{canonical_hash, position_hash, unique_moves, Symmetry};
+
+    fn play(board: &mut Board, player: Player, row: usize, col: usize) {
+        board.play(&Move::new(player, Loc { row, col }));
+    }
+
+    #[test]
+    fn every_symmetry_is_undone_by_its_inverse() {
+        let size = (11, 11);
+        let loc = Loc { row: 2, col: 4 };
+        let mut images = vec![];
+        for symmetry in Symmetry::ALL {
+            let image = symmetry.apply(loc, size);
+            assert!(image.is_on_board(size));
+            assert_eq!(symmetry.inverse().apply(image, size), loc);
+            images.push(image);
+        }
+        images.sort();
+        images.dedup();
+        assert_eq!(images.len(), 8);
+        assert_eq!(
+            Symmetry::Rotate90.apply(Loc { row: 1, col: 1 }, size),
+            Loc { row: 1, col: 9 }
+        );
+        let pass = Move::new(Player::Black, Loc { row: 1, col: 1 }).pass();
+        assert_eq!(Symmetry::Transpose.apply_move(&pass, size), pass);
+        // A 5x3 board turns into a 3x5 board
+        let image = Symmetry::Rotate270.apply(Loc { row: 1, col: 3 }, (7, 5));
+        assert_eq!(image, Loc { row: 1, col: 1 });
+        assert!(image.is_on_board(Symmetry::Rotate270.board_size((7, 5))));
+    }
+
+    #[test]
+    fn transformed_boards_keep_captures_and_history() {
+        let mut board = Board::new(7, 7, 0.5);
+        play(&mut board, Player::Black, 1, 2);
+        play(&mut board, Player::White, 1, 1);
+        play(&mut board, Player::Black, 2, 1);
+        assert!(board.get_captures(Player::Black) == 1);
+
+        let rotated = board.transform(Symmetry::Rotate90);
+        assert!(rotated.get_captures(Player::Black) == 1);
+        assert_eq!(
+            rotated.get(Loc { row: 2, col: 5 }),
+            board.get(Loc { row: 1, col: 2 })
+        );
+        assert_eq!(
+            rotated.get_game_history()[1].get_loc(),
+            Some(Loc { row: 1, col: 5 })
+        );
+        assert!(rotated.transform(Symmetry::Rotate270) == board);
+
+        let set_up = Board::from_diagram("O # .|. . .|. . .; White to play, ko 1 3").unwrap();
+        let flipped = set_up.transform(Symmetry::FlipCols);
+        assert!(flipped
+            .to_diagram()
+            .starts_with("White to play, komi 0, ko 1 1\n. # O\n"));
+    }
+
+    #[test]
+    fn symmetric_positions_share_the_canonical_hash() {
+        let mut board = Board::new(9, 11, 0.5);
+        play(&mut board, Player::Black, 2, 3);
+        play(&mut board, Player::White, 5, 5);
+        let (hash, _) = canonical_hash(&board);
+        for symmetry in Symmetry::ALL {
+            let copy = board.transform(symmetry);
+            assert_eq!(canonical_hash(&copy).0, hash);
+            assert_eq!(
+                position_hash(&board, symmetry),
+                position_hash(&copy, Symmetry::Identity)
+            );
+        }
+
+        let mut other = Board::new(9, 11, 0.5);
+        play(&mut other, Player::Black, 2, 4);
+        play(&mut other, Player::White, 5, 5);
+        assert_ne!(canonical_hash(&other).0, hash);
+    }
+
+    #[test]
+    fn symmetric_moves_are_pruned_on_symmetric_positions() {
+        let mut board = Board::new(11, 11, 0.5);
+        let (rows, cols) = board.board_size();
+        let points: Vec<Loc> = Loc::get_all_on_board(rows, cols)
+            .into_iter()
+            .filter(|loc| loc.is_on_board((rows, cols)))
+            .collect();
+        // One triangle of the 9x9 board, the center line included
+        assert_eq!(unique_moves(&board, &points).len(), 15);
+
+        play(&mut board, Player::Black, 3, 3);
+        // Only the diagonal through the stone is left
+        assert_eq!(unique_moves(&board, &points).len(), (81 + 9) / 2);
+    }
+}
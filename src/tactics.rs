@@ -0,0 +1,248 @@
+// Reading of fights around a single group: ladders, and small capture and escape searches.
+// Lingo:
+//     target   - the group under attack, named by any of its stones
+//     attacker - the player who wants to capture the target, the defender owns it
+//     ladder   - a chase where every attacker move is atari and the defender can only run or capture
+//     depth    - the number of moves read ahead, both players' moves count
+// Only moves on the liberties of the target and captures of attacker stones next to it are read,
+// so a group with many liberties counts as safe. Unknown at the depth limit counts as safe as well.
+
+use crate::belief::sample_determinization;
+use crate::board::{Board, Color, Loc, Move, MoveKind, Player};
+use crate::knowledge::KnowledgeModel;
+use crate::seed::GameRng;
+
+// Attacks on groups with more liberties than this aren't read
+const MAX_LIBERTIES: usize = 3;
+// Ladders run until the edge, this covers the longest one on a 19x19 board
+const LADDER_DEPTH: usize = 2 * 19 * 19;
+
+// The player whose stone is at target, None for empty points and sentinels
+pub(crate) fn owner(board: &Board, target: Loc) -> Option<Player> {
+    match board.get(target) {
+        Color::Black => Some(Player::Black),
+        Color::White => Some(Player::White),
+        _ => None,
+    }
+}
+
+// The board after the move, None when it's illegal
+fn after(board: &Board, player: Player, loc: Loc) -> Option<Board> {
+    let mv = Move::new(player, loc);
+    if !board.move_is_valid(&mv) {
+        return None;
+    }
+    let mut next = board.clone();
+    next.play(&mv);
+    Some(next)
+}
+
+// Liberties of the target and the last liberty of every attacker group in atari next to it
+fn defender_moves(board: &Board, target: Loc) -> Vec<Loc> {
+    let defender = board.get(target);
+    let mut moves = board.get_liberties(target);
+    for stone in board.group_stones(target) {
+        for nbr in stone.all_4nbr() {
+            let color = board.get(nbr);
+            if color != defender && matches!(color, Color::Black | Color::White) {
+                let liberties = board.get_liberties(nbr);
+                if liberties.len() == 1 && !moves.contains(&liberties[0]) {
+                    moves.push(liberties[0]);
+                }
+            }
+        }
+    }
+    moves
+}
+
+// The attacker is to move, Some with the first move of a capture that works against every defence
+fn attack(board: &Board, target: Loc, depth: usize, ladder: bool) -> Option<Loc> {
+    let attacker = owner(board, target)?.opponent();
+    let liberties = board.get_liberties(target);
+    if liberties.len() == 1 && after(board, attacker, liberties[0]).is_some() {
+        return Some(liberties[0]);
+    }
+    let max_liberties = if ladder { 2 } else { MAX_LIBERTIES };
+    if depth == 0 || liberties.len() > max_liberties {
+        return None;
+    }
+    liberties
+        .into_iter()
+        .find(|loc| match after(board, attacker, *loc) {
+            Some(next) if ladder && next.count_liberties(target) != 1 => false,
+            Some(next) => defend(&next, target, depth - 1, ladder).is_none(),
+            None => false,
+        })
+}
+
+// The defender is to move, Some with a move that saves the target, a pass when it needs none
+fn defend(board: &Board, target: Loc, depth: usize, ladder: bool) -> Option<MoveKind> {
+    let defender = owner(board, target)?;
+    if depth == 0 {
+        return Some(MoveKind::Pass);
+    }
+    if !ladder && board.count_liberties(target) > 1 {
+        let mut next = board.clone();
+        next.set_current_player(defender.opponent());
+        if attack(&next, target, depth - 1, ladder).is_none() {
+            return Some(MoveKind::Pass);
+        }
+    }
+    defender_moves(board, target)
+        .into_iter()
+        .find(|loc| match after(board, defender, *loc) {
+            Some(next) => {
+                next.get(target) != Color::Empty
+                    && attack(&next, target, depth - 1, ladder).is_none()
+            }
+            None => false,
+        })
+        .map(MoveKind::Play)
+}
+
+fn with_player_to_move(board: &Board, player: Player) -> Board {
+    let mut board = board.without_history();
+    board.set_current_player(player);
+    board
+}
+
+// Whether the attacker captures the target by chasing it with ataris, whoever is to move.
+// With the attacker to move the target may have two liberties, with the defender to move only one
+pub fn ladder_works(board: &Board, target: Loc) -> bool {
+    let defender = match owner(board, target) {
+        Some(player) => player,
+        None => return false,
+    };
+    let board = board.without_history();
+    if board.get_current_player() == defender {
+        board.count_liberties(target) == 1 && defend(&board, target, LADDER_DEPTH, true).is_none()
+    } else {
+        attack(&board, target, LADDER_DEPTH, true).is_some()
+    }
+}
+
+// The attacker's first move of a capture within depth moves, with the attacker to move
+pub fn can_capture(board: &Board, target: Loc, depth: usize) -> Option<Loc> {
+    let attacker = owner(board, target)?.opponent();
+    attack(&with_player_to_move(board, attacker), target, depth, false)
+}
+
+// The defender's move that keeps the target alive for depth moves, a pass when it needs none
+pub fn can_escape(board: &Board, target: Loc, depth: usize) -> Option<MoveKind> {
+    let defender = owner(board, target)?;
+    defend(&with_player_to_move(board, defender), target, depth, false)
+}
+
+// For phantom play: in how many sampled determinizations the opponent can capture the own group at target,
+// None when no determinization could be sampled
+pub fn capture_probability(
+    knowledge: &KnowledgeModel,
+    target: Loc,
+    depth: usize,
+    samples: usize,
+    rng: &mut GameRng,
+) -> Option<f64> {
+    let mut accepted = 0;
+    let mut captured = 0;
+    for _ in 0..samples {
+        if let Some(board) = sample_determinization(knowledge, rng) {
+            accepted += 1;
+            if can_capture(&board, target, depth).is_some() {
+                captured += 1;
+            }
+        }
+    }
+    (accepted > 0).then(|| captured as f64 / accepted as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, MoveKind, Player};
+    use crate::knowledge::KnowledgeModel;
+    use crate::referee::{Announcement, Event};
+    use crate::seed::new_rng;
+    use crate::tactics::{can_capture, can_escape, capture_probability, ladder_works};
+
+    // White's stone at 3, 3 has two liberties, chased it runs towards the bottom right
+    const LADDER: &str = "
+        Black to play
+        . . . . . . . . .
+        . . O O . . . . .
+        . O # . . . . . .
+        . . . . . . . . .
+        . . . . . . . . .
+        . . . . . . . . .
+        . . . . . . . . .
+        . . . . . . . . .
+        . . . . . . . . .";
+
+    #[test]
+    fn ladders_work_until_a_stone_breaks_them() {
+        let target = Loc { row: 3, col: 3 };
+        let board = Board::from_diagram(LADDER).unwrap();
+        assert!(ladder_works(&board, target));
+        assert!(can_capture(&board, target, 3).is_none());
+
+        let mut broken = board.clone();
+        broken.place_stone(Loc { row: 7, col: 7 }, Player::White);
+        assert!(!ladder_works(&broken, target));
+    }
+
+    #[test]
+    fn groups_escape_by_capturing_the_stone_that_ataris_them() {
+        let board = Board::from_diagram(
+            "White to play
+             O # O . .
+             . . O . .
+             # O . . .
+             . . . . .
+             . . . . .",
+        )
+        .unwrap();
+        let target = Loc { row: 1, col: 2 };
+        assert_eq!(can_capture(&board, target, 4), Some(Loc { row: 2, col: 2 }));
+        // Running out at 2, 2 stays in atari, taking the stone in the corner gives two liberties
+        assert_eq!(
+            can_escape(&board, target, 2),
+            Some(MoveKind::Play(Loc { row: 2, col: 1 }))
+        );
+        // Reading further, Black ataris again at 2, 2 and the white stones run out of room in the corner
+        assert_eq!(can_escape(&board, target, 4), None);
+    }
+
+    #[test]
+    fn capture_chances_are_read_on_determinizations() {
+        let mut knowledge = KnowledgeModel::new(Player::Black, 11, 11);
+        let played = |loc| Announcement {
+            mover: Player::Black,
+            event: Event::Played(loc),
+        };
+        knowledge.update(&played(Loc { row: 1, col: 1 }));
+        let mut rng = new_rng(3);
+        // Nothing of White is on the board, a lone stone in the corner survives
+        assert_eq!(
+            capture_probability(&knowledge, Loc { row: 1, col: 1 }, 4, 8, &mut rng),
+            Some(0.0)
+        );
+
+        // Somewhere on a small board is a white stone, next to the corner it captures
+        let mut knowledge = KnowledgeModel::new(Player::Black, 7, 7);
+        knowledge.update(&played(Loc { row: 1, col: 1 }));
+        knowledge.update(&Announcement {
+            mover: Player::White,
+            event: Event::Moved,
+        });
+        let chance =
+            capture_probability(&knowledge, Loc { row: 1, col: 1 }, 4, 64, &mut rng).unwrap();
+        assert!(chance > 0.0 && chance < 1.0);
+
+        knowledge.update(&Announcement {
+            mover: Player::Black,
+            event: Event::Occupied(Loc { row: 1, col: 2 }),
+        });
+        assert_eq!(
+            capture_probability(&knowledge, Loc { row: 1, col: 1 }, 4, 8, &mut rng),
+            Some(1.0)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+// Lingo:
+//     pairing - two entrants that play games against each other
+//     score   - wins plus half of the draws
+
+use crate::agent::Agent;
+use crate::board::{GameResult, Player};
+use crate::match_runner::{play_game, GameRecord, MatchConfig};
+use crate::seed::derive_seed;
+use std::fmt;
+
+// Virtual draws added to every pairing, keeps ratings finite when someone wins or loses everything
+const PRIOR_DRAWS: f64 = 1.0;
+const MAX_ITERATIONS: usize = 10000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    RoundRobin,
+    // The first entrant plays everyone else, the others don't play each other
+    Gauntlet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    // Half width of the 95% confidence interval
+    pub error: f64,
+}
+
+pub struct Tournament {
+    pub schedule: Schedule,
+    // Colors alternate between the games of a pairing
+    pub games_per_pairing: usize,
+    pub config: MatchConfig,
+    // Game k is played with derive_seed(seed, k)
+    pub seed: u64,
+}
+
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    // scores[i][j] is the score of entrant i against entrant j
+    pub scores: Vec<Vec<f64>>,
+    pub games: Vec<Vec<usize>>,
+    pub ratings: Vec<Rating>,
+    pub records: Vec<GameRecord>,
+}
+
+fn two_agents(
+    agents: &mut [Box<dyn Agent>],
+    i: usize,
+    j: usize,
+) -> (&mut dyn Agent, &mut dyn Agent) {
+    if i < j {
+        let (left, right) = agents.split_at_mut(j);
+        (left[i].as_mut(), right[0].as_mut())
+    } else {
+        let (left, right) = agents.split_at_mut(i);
+        (right[0].as_mut(), left[j].as_mut())
+    }
+}
+
+impl Tournament {
+    pub fn new(
+        schedule: Schedule,
+        games_per_pairing: usize,
+        config: MatchConfig,
+        seed: u64,
+    ) -> Self {
+        Tournament {
+            schedule,
+            games_per_pairing,
+            config,
+            seed,
+        }
+    }
+
+    pub fn pairings(&self, entrants: usize) -> Vec<(usize, usize)> {
+        let mut pairings = vec![];
+        for i in 0..entrants {
+            for j in i + 1..entrants {
+                if self.schedule == Schedule::RoundRobin || i == 0 {
+                    pairings.push((i, j));
+                }
+            }
+        }
+        pairings
+    }
+
+    pub fn run(&self, agents: &mut [Box<dyn Agent>]) -> TournamentResult {
+        let n = agents.len();
+        let mut scores = vec![vec![0.0; n]; n];
+        let mut games = vec![vec![0; n]; n];
+        let mut records = vec![];
+
+        for (i, j) in self.pairings(n) {
+            for game in 0..self.games_per_pairing {
+                let (black, white) = if game % 2 == 0 { (i, j) } else { (j, i) };
+                let (black_agent, white_agent) = two_agents(agents, black, white);
+                let game_seed = derive_seed(self.seed, records.len() as u64);
+                let record = play_game(black_agent, white_agent, &self.config, game_seed);
+
+                // Void games are kept in the records but don't count
+                if record.result != GameResult::Void {
+                    match record.winner() {
+                        Some(Player::Black) => scores[black][white] += 1.0,
+                        Some(Player::White) => scores[white][black] += 1.0,
+                        None => {
+                            scores[black][white] += 0.5;
+                            scores[white][black] += 0.5;
+                        }
+                    }
+                    games[i][j] += 1;
+                    games[j][i] += 1;
+                }
+                records.push(record);
+            }
+        }
+
+        TournamentResult {
+            names: agents.iter().map(|agent| agent.name()).collect(),
+            ratings: fit_elo(&scores, &games),
+            scores,
+            games,
+            records,
+        }
+    }
+}
+
+// Bradley-Terry maximum likelihood with minorization-maximization iterations, anchored at an average of 0 Elo.
+// Error bars come from the diagonal of the Fisher information.
+pub fn fit_elo(scores: &[Vec<f64>], games: &[Vec<usize>]) -> Vec<Rating> {
+    let n = scores.len();
+    let mut wins = vec![vec![0.0; n]; n];
+    let mut played = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if games[i][j] > 0 {
+                wins[i][j] = scores[i][j] + PRIOR_DRAWS / 2.0;
+                played[i][j] = games[i][j] as f64 + PRIOR_DRAWS;
+            }
+        }
+    }
+
+    let mut gammas = vec![1.0; n];
+    for _ in 0..MAX_ITERATIONS {
+        let mut max_change: f64 = 0.0;
+        for i in 0..n {
+            let total_wins: f64 = wins[i].iter().sum();
+            let denominator: f64 = (0..n)
+                .filter(|j| played[i][*j] > 0.0)
+                .map(|j| played[i][j] / (gammas[i] + gammas[j]))
+                .sum();
+            if denominator == 0.0 {
+                continue;
+            }
+            let new_gamma = total_wins / denominator;
+            max_change = max_change.max((new_gamma / gammas[i]).ln().abs());
+            gammas[i] = new_gamma;
+        }
+
+        let mean_log = gammas.iter().map(|gamma| gamma.ln()).sum::<f64>() / n as f64;
+        for gamma in gammas.iter_mut() {
+            *gamma /= mean_log.exp();
+        }
+        if max_change < 1e-10 {
+            break;
+        }
+    }
+
+    let elo_per_log = 400.0 / 10f64.ln();
+    (0..n)
+        .map(|i| {
+            let information: f64 = (0..n)
+                .map(|j| {
+                    let p = gammas[i] / (gammas[i] + gammas[j]);
+                    played[i][j] * p * (1.0 - p)
+                })
+                .sum();
+            let error = if information > 0.0 {
+                1.96 * elo_per_log / information.sqrt()
+            } else {
+                f64::INFINITY
+            };
+            Rating {
+                elo: elo_per_log * gammas[i].ln(),
+                error,
+            }
+        })
+        .collect()
+}
+
+impl TournamentResult {
+    pub fn total_score(&self, i: usize) -> f64 {
+        self.scores[i].iter().sum()
+    }
+
+    pub fn total_games(&self, i: usize) -> usize {
+        self.games[i].iter().sum()
+    }
+
+    // Entrant indices from the highest rating down
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|a, b| self.ratings[*b].elo.total_cmp(&self.ratings[*a].elo));
+        order
+    }
+}
+
+// The crosstable
+impl fmt::Display for TournamentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let order = self.standings();
+        let name_width = self
+            .names
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        write!(
+            f,
+            "{:>3}  {:<name_width$} {:>7} {:>6} {:>7} |",
+            "#", "Name", "Elo", "+/-", "Score"
+        )?;
+        for rank in 1..=order.len() {
+            write!(f, " {:>7}", rank)?;
+        }
+        writeln!(f)?;
+
+        for (rank, i) in order.iter().enumerate() {
+            let rating = self.ratings[*i];
+            write!(
+                f,
+                "{:>3}  {:<name_width$} {:>7.1} {:>6.1} {:>7} |",
+                rank + 1,
+                self.names[*i],
+                rating.elo,
+                rating.error,
+                format!("{}/{}", self.total_score(*i), self.total_games(*i))
+            )?;
+            for j in &order {
+                if i == j || self.games[*i][*j] == 0 {
+                    write!(f, " {:>7}", "-")?;
+                } else {
+                    write!(
+                        f,
+                        " {:>7}",
+                        format!("{}/{}", self.scores[*i][*j], self.games[*i][*j])
+                    )?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{Agent, PassAgent, RandomAgent};
+    use crate::match_runner::MatchConfig;
+    use crate::tournament::{fit_elo, Schedule, Tournament};
+
+    #[test]
+    fn equal_results_give_equal_ratings() {
+        let scores = vec![vec![0.0, 5.0], vec![5.0, 0.0]];
+        let games = vec![vec![0, 10], vec![10, 0]];
+
+        let ratings = fit_elo(&scores, &games);
+        assert!(ratings[0].elo.abs() < 1e-6);
+        assert!(ratings[1].elo.abs() < 1e-6);
+        assert!(ratings[0].error > 0.0 && ratings[0].error.is_finite());
+    }
+
+    #[test]
+    fn winning_three_quarters_is_worth_about_190_elo() {
+        let scores = vec![vec![0.0, 7500.0], vec![2500.0, 0.0]];
+        let games = vec![vec![0, 10000], vec![10000, 0]];
+
+        let ratings = fit_elo(&scores, &games);
+        let difference = ratings[0].elo - ratings[1].elo;
+        assert!((difference - 400.0 * 3f64.log10()).abs() < 0.5);
+        assert!((ratings[0].elo + ratings[1].elo).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gauntlet_pairs_only_the_first_entrant() {
+        let tournament = Tournament::new(Schedule::Gauntlet, 2, MatchConfig::new(5, 5, 0.5), 0);
+        assert_eq!(tournament.pairings(4), vec![(0, 1), (0, 2), (0, 3)]);
+
+        let tournament = Tournament::new(Schedule::RoundRobin, 2, MatchConfig::new(5, 5, 0.5), 0);
+        assert_eq!(tournament.pairings(3), vec![(0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn round_robin_plays_every_pairing_and_writes_a_crosstable() {
+        let tournament = Tournament::new(Schedule::RoundRobin, 4, MatchConfig::new(6, 6, 0.5), 7);
+        let mut agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(RandomAgent::new(0)),
+            Box::new(PassAgent),
+            Box::new(RandomAgent::with_pass_probability(0.5, 0)),
+        ];
+
+        let result = tournament.run(&mut agents);
+        assert_eq!(result.records.len(), 12);
+        for i in 0..3 {
+            assert_eq!(result.total_games(i), 8);
+        }
+        let total_score: f64 = (0..3).map(|i| result.total_score(i)).sum();
+        assert_eq!(total_score, 12.0);
+
+        let crosstable = result.to_string();
+        assert!(crosstable.contains("random-pass0.5"));
+        assert_eq!(crosstable.lines().count(), 4);
+
+        let rerun = tournament.run(&mut agents);
+        assert_eq!(rerun.scores, result.scores);
+        assert_eq!(rerun.to_string(), crosstable);
+    }
+}
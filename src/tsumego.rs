@@ -0,0 +1,460 @@
+// Life and death of a group in an enclosed region, read out to the end.
+// Lingo:
+//     target    - the group whose life is in question, named by any of its stones
+//     region    - the points the problem is played on, moves outside of it aren't read
+//     kill      - the attacker captures the target whatever the defender does
+//     live      - the target can't be captured, seki - it lives and so do the attacker stones it shares liberties with
+//     ko        - the result depends on who wins a ko, that is who has more ko threats elsewhere
+//     variation - the solution, best play for both players starting with the player to move
+// The search is alpha-beta with proven results only in a transposition table.
+// The attacker never passes, a pass would let the defender end the problem alive.
+// Who wins a ko is read twice, once with each player winning every ko,
+// the winner may retake a ko at once as if a threat had been answered elsewhere.
+
+use crate::belief::sample_determinization;
+use crate::board::{Board, Color, Loc, Move, MoveKind, Player};
+use crate::knowledge::KnowledgeModel;
+use crate::seed::GameRng;
+use crate::symmetry::{position_hash, Symmetry};
+use crate::tactics::owner;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Kill,
+    Live,
+    Ko,
+    Seki,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            Status::Kill => "kill",
+            Status::Live => "live",
+            Status::Ko => "ko",
+            Status::Seki => "seki",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub status: Status,
+    pub variation: Vec<Move>,
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.status)?;
+        for mv in &self.variation {
+            match mv.kind {
+                MoveKind::Play(loc) => write!(f, ", {:?} {} {}", mv.player, loc.row, loc.col)?,
+                MoveKind::Pass => write!(f, ", {:?} passes", mv.player)?,
+                MoveKind::Resign => write!(f, ", {:?} resigns", mv.player)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Killed,
+    Lives,
+    // The move limit came first
+    Unknown,
+}
+
+struct Search<'a> {
+    region: &'a [Loc],
+    target: Loc,
+    defender: Player,
+    // The player who wins every ko
+    threats: Player,
+    // Proven outcomes with the best move, by position and ko point
+    table: HashMap<(u64, Option<Loc>), (Outcome, MoveKind)>,
+}
+
+impl Search<'_> {
+    fn key(board: &Board) -> (u64, Option<Loc>) {
+        (position_hash(board, Symmetry::Identity), board.get_ko())
+    }
+
+    // The board after the move, None when it's illegal, ko is the ko point of the board's key
+    fn after(&self, board: &Board, ko: Option<Loc>, kind: MoveKind) -> Option<Board> {
+        let player = board.get_current_player();
+        let mv = Move { player, kind };
+        let mut next = if player == self.threats && ko.is_some() && mv.get_loc() == ko {
+            board.without_ko()
+        } else {
+            board.clone()
+        };
+        if !next.move_is_valid(&mv) {
+            return None;
+        }
+        next.play(&mv);
+        Some(next)
+    }
+
+    // Empty points of the region, the liberties of the target first, and a pass for the defender
+    fn moves(&self, board: &Board) -> Vec<MoveKind> {
+        let liberties = board.get_liberties(self.target);
+        let mut points = liberties.clone();
+        points.retain(|loc| self.region.contains(loc));
+        for loc in self.region {
+            if board.get(*loc) == Color::Empty && !liberties.contains(loc) {
+                points.push(*loc);
+            }
+        }
+        let mut moves: Vec<MoveKind> = points.into_iter().map(MoveKind::Play).collect();
+        if board.get_current_player() == self.defender {
+            moves.push(MoveKind::Pass);
+        }
+        moves
+    }
+
+    fn search(&mut self, board: &Board, depth: usize) -> Outcome {
+        let key = Search::key(board);
+        if let Some((outcome, _)) = self.table.get(&key) {
+            return *outcome;
+        }
+        if depth == 0 {
+            return Outcome::Unknown;
+        }
+        let attacking = board.get_current_player() != self.defender;
+        let (win, loss) = if attacking {
+            (Outcome::Killed, Outcome::Lives)
+        } else {
+            (Outcome::Lives, Outcome::Killed)
+        };
+        // A losing attacker gives up with a pass, a losing defender shows the longest resistance it found
+        let mut result = loss;
+        let mut best = MoveKind::Pass;
+        for kind in self.moves(board) {
+            let next = match self.after(board, key.1, kind) {
+                Some(next) => next,
+                None => continue,
+            };
+            let outcome = if next.get(self.target) != self.defender.to_color() {
+                Outcome::Killed
+            } else {
+                self.search(&next, depth - 1)
+            };
+            if outcome == win {
+                result = win;
+                best = kind;
+                break;
+            }
+            if outcome == Outcome::Unknown {
+                result = Outcome::Unknown;
+            } else if !attacking && best == MoveKind::Pass {
+                best = kind;
+            }
+        }
+        if result != Outcome::Unknown {
+            self.table.insert(key, (result, best));
+        }
+        result
+    }
+
+    // Best moves from the table until the problem is decided
+    fn variation(&self, board: &Board, max_moves: usize) -> Vec<Move> {
+        let mut board = board.clone();
+        let mut variation = vec![];
+        while variation.len() < max_moves {
+            let key = Search::key(&board);
+            let kind = match self.table.get(&key) {
+                Some((_, kind)) => *kind,
+                None => break,
+            };
+            let player = board.get_current_player();
+            variation.push(Move { player, kind });
+            if kind == MoveKind::Pass && player != self.defender {
+                break;
+            }
+            board = match self.after(&board, key.1, kind) {
+                Some(next) => next,
+                None => break,
+            };
+            if board.get(self.target) != self.defender.to_color() {
+                break;
+            }
+        }
+        variation
+    }
+}
+
+// Reads the problem with one player winning every ko, the outcome and the variation
+fn read(
+    board: &Board,
+    region: &[Loc],
+    target: Loc,
+    threats: Player,
+    max_moves: usize,
+) -> (Outcome, Vec<Move>) {
+    let mut search = Search {
+        region,
+        target,
+        defender: owner(board, target).unwrap(),
+        threats,
+        table: HashMap::new(),
+    };
+    let outcome = search.search(board, max_moves);
+    (outcome, search.variation(board, max_moves))
+}
+
+// An attacker group next to the living target that can't be captured either, whoever starts
+fn is_seki(board: &Board, region: &[Loc], target: Loc, max_moves: usize) -> bool {
+    let attacker = match owner(board, target) {
+        Some(defender) => defender.opponent(),
+        None => return false,
+    };
+    let liberties = board.get_liberties(target);
+    // One stone of each attacker group that shares a liberty with the target
+    let mut seen = vec![];
+    let mut groups = vec![];
+    for loc in region {
+        if board.get(*loc) != attacker.to_color() || seen.contains(loc) {
+            continue;
+        }
+        seen.extend(board.group_stones(*loc));
+        if board
+            .get_liberties(*loc)
+            .iter()
+            .any(|lib| liberties.contains(lib))
+        {
+            groups.push(*loc);
+        }
+    }
+    groups.iter().any(|loc| {
+        [Player::Black, Player::White].into_iter().all(|player| {
+            let mut board = board.without_history();
+            board.set_current_player(player);
+            matches!(
+                solve_status(&board, region, *loc, max_moves, false),
+                Some(Solution {
+                    status: Status::Live,
+                    ..
+                })
+            )
+        })
+    })
+}
+
+fn solve_status(
+    board: &Board,
+    region: &[Loc],
+    target: Loc,
+    max_moves: usize,
+    seki: bool,
+) -> Option<Solution> {
+    let defender = owner(board, target)?;
+    let board = board.without_history();
+    let (killed, kill_variation) = read(&board, region, target, defender, max_moves);
+    if killed == Outcome::Killed {
+        return Some(Solution {
+            status: Status::Kill,
+            variation: kill_variation,
+        });
+    }
+    let (lives, live_variation) = read(&board, region, target, defender.opponent(), max_moves);
+    if lives == Outcome::Lives {
+        let mut end = board.clone();
+        for mv in &live_variation {
+            end.play(mv);
+        }
+        let status = if seki && is_seki(&end, region, target, max_moves) {
+            Status::Seki
+        } else {
+            Status::Live
+        };
+        return Some(Solution {
+            status,
+            variation: live_variation,
+        });
+    }
+    if killed == Outcome::Lives && lives == Outcome::Killed {
+        // The variation of the player to move winning the ko
+        let variation = if board.get_current_player() == defender {
+            kill_variation
+        } else {
+            live_variation
+        };
+        return Some(Solution {
+            status: Status::Ko,
+            variation,
+        });
+    }
+    None
+}
+
+// The status of the target with the player to move of the board starting, moves only on the region.
+// None without a stone on target or when max_moves aren't enough to read the problem out
+pub fn solve(board: &Board, region: &[Loc], target: Loc, max_moves: usize) -> Option<Solution> {
+    solve_status(board, region, target, max_moves, true)
+}
+
+// For phantom problems: the status the player of the knowledge model gets whatever the unseen stones are,
+// None when sampled determinizations disagree, can't be read out, or none could be sampled
+pub fn phantom_status(
+    knowledge: &KnowledgeModel,
+    region: &[Loc],
+    target: Loc,
+    max_moves: usize,
+    samples: usize,
+    rng: &mut GameRng,
+) -> Option<Status> {
+    let mut status = None;
+    for _ in 0..samples {
+        let board = match sample_determinization(knowledge, rng) {
+            Some(board) => board,
+            None => continue,
+        };
+        let sampled = solve(&board, region, target, max_moves)?.status;
+        if status.is_some_and(|status| status != sampled) {
+            return None;
+        }
+        status = Some(sampled);
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, Move, MoveKind, Player};
+    use crate::knowledge::KnowledgeModel;
+    use crate::referee::{Announcement, Event};
+    use crate::seed::new_rng;
+    use crate::tsumego::{phantom_status, solve, Status};
+
+    fn row(row: usize, cols: std::ops::RangeInclusive<usize>) -> Vec<Loc> {
+        cols.map(|col| Loc { row, col }).collect()
+    }
+
+    #[test]
+    fn straight_three_dies_or_lives_by_its_vital_point() {
+        let diagram = ". . . # O|# # # # O|O O O O O|. . . . .|. . . . .";
+        let target = Loc { row: 2, col: 2 };
+        let region = row(1, 1..=3);
+        let vital = Loc { row: 1, col: 2 };
+
+        let kill = solve(
+            &Board::from_diagram(&format!("{}; Black to play", diagram)).unwrap(),
+            &region,
+            target,
+            10,
+        )
+        .unwrap();
+        assert_eq!(kill.status, Status::Kill);
+        assert_eq!(kill.variation[0].get_loc(), Some(vital));
+        assert!(kill.variation.len() % 2 == 1);
+
+        let live = solve(
+            &Board::from_diagram(&format!("{}; White to play", diagram)).unwrap(),
+            &region,
+            target,
+            10,
+        )
+        .unwrap();
+        assert_eq!(live.status, Status::Live);
+        assert_eq!(live.variation[0].get_loc(), Some(vital));
+        assert!(live.variation.last().unwrap().is_pass());
+    }
+
+    #[test]
+    fn shared_liberties_without_eyes_are_seki() {
+        // Filling a shared liberty lets the other side capture four stones in a row, enough for two eyes
+        let board = Board::from_diagram(
+            "Black to play
+             . O O O . # O . .
+             # # # # # # O . .
+             O O O O O O O . .
+             . . . . . . . . .",
+        )
+        .unwrap();
+        let solution = solve(&board, &row(1, 1..=5), Loc { row: 2, col: 2 }, 20).unwrap();
+        assert_eq!(solution.status, Status::Seki);
+        assert_eq!(
+            solution.variation,
+            vec![Move {
+                player: Player::Black,
+                kind: MoveKind::Pass
+            }]
+        );
+    }
+
+    #[test]
+    fn an_eye_that_can_be_retaken_is_a_ko() {
+        let board = Board::from_diagram(
+            "White to play
+             . # O . O
+             # # # O O
+             O O O O .
+             . . . . .",
+        )
+        .unwrap();
+        let target = Loc { row: 2, col: 2 };
+        let region = [
+            Loc { row: 1, col: 1 },
+            Loc { row: 1, col: 3 },
+            Loc { row: 1, col: 4 },
+        ];
+        let solution = solve(&board, &region, target, 10).unwrap();
+        assert_eq!(solution.status, Status::Ko);
+        assert_eq!(
+            solution.variation[0].get_loc(),
+            Some(Loc { row: 1, col: 4 })
+        );
+        assert!(solve(&board, &region, Loc { row: 4, col: 4 }, 10).is_none());
+    }
+
+    #[test]
+    fn phantom_problems_are_solved_only_when_every_determinization_agrees() {
+        // Black knows the straight three of the first test, except for the White stone at 1 4 when hiding
+        let knowledge = |hiding: bool| {
+            let mut knowledge = KnowledgeModel::new(Player::Black, 7, 7);
+            let announce = |knowledge: &mut KnowledgeModel, mover, event| {
+                knowledge.update(&Announcement { mover, event });
+            };
+            for loc in [(1, 5), (2, 5), (3, 1), (3, 2), (3, 3), (3, 4), (3, 5)] {
+                let loc = Loc {
+                    row: loc.0,
+                    col: loc.1,
+                };
+                announce(&mut knowledge, Player::Black, Event::Played(loc));
+            }
+            for loc in [(1, 4), (2, 1), (2, 2), (2, 3), (2, 4)] {
+                announce(&mut knowledge, Player::White, Event::Moved);
+                if !(hiding && loc == (1, 4)) {
+                    let loc = Loc {
+                        row: loc.0,
+                        col: loc.1,
+                    };
+                    announce(&mut knowledge, Player::Black, Event::Occupied(loc));
+                }
+            }
+            knowledge
+        };
+        let target = Loc { row: 2, col: 2 };
+        let region = row(1, 1..=3);
+        let mut rng = new_rng(6);
+
+        let known = knowledge(false);
+        assert_eq!(
+            phantom_status(&known, &region, target, 10, 5, &mut rng),
+            Some(Status::Kill)
+        );
+        assert_eq!(
+            phantom_status(&known, &region, Loc { row: 4, col: 4 }, 10, 5, &mut rng),
+            None
+        );
+        // The hidden stone may be inside the eye space as well as far away
+        assert_eq!(
+            phantom_status(&knowledge(true), &region, target, 10, 20, &mut rng),
+            None
+        );
+    }
+}
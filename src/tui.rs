@@ -0,0 +1,399 @@
+// Full-screen local game, the arrow keys move a cursor and Enter places a stone.
+// Lingo:
+//     cursor   - the point the next stone goes to
+//     panel    - the column next to the board with the clock, captures, moves and messages
+//     counting - the phase after two passes where dead groups are taken off before scoring
+
+use crate::board::{Board, Color, GameResult, Loc, Move, MoveKind, Player};
+use crate::clock::{Clock, TimeControl};
+use crate::variant::Variant;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color as TermColor, Stylize};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+
+const PANEL_MOVES: usize = 10;
+const PANEL_MESSAGES: usize = 5;
+const BOARD_BACKGROUND: TermColor = TermColor::Rgb {
+    r: 220,
+    g: 179,
+    b: 92,
+};
+const CURSOR_BACKGROUND: TermColor = TermColor::Rgb {
+    r: 230,
+    g: 90,
+    b: 60,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Phase {
+    Playing,
+    Counting,
+    Over(GameResult),
+}
+
+pub struct LocalGame {
+    board: Board,
+    variant: Variant,
+    clock: Clock,
+    cursor: Loc,
+    phase: Phase,
+    messages: Vec<String>,
+}
+
+fn describe_move(mv: &Move) -> String {
+    match mv.kind {
+        MoveKind::Play(loc) => format!("{:?} {}, {}", mv.player, loc.row, loc.col),
+        MoveKind::Pass => format!("{:?} passes", mv.player),
+        MoveKind::Resign => format!("{:?} resigns", mv.player),
+    }
+}
+
+impl LocalGame {
+    // The clock starts for the player to move right away
+    pub fn new(board: Board, variant: Variant, mut clock: Clock) -> Self {
+        let (rows, cols) = board.board_size();
+        clock.start(board.get_current_player());
+        LocalGame {
+            cursor: Loc {
+                row: rows / 2,
+                col: cols / 2,
+            },
+            board,
+            variant,
+            clock,
+            phase: Phase::Playing,
+            messages: vec![],
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn get_cursor(&self) -> Loc {
+        self.cursor
+    }
+
+    // The cursor stays on the board, sentinels excluded
+    fn move_cursor(&mut self, rows_down: isize, cols_right: isize) {
+        let (rows, cols) = self.board.board_size();
+        let row = self.cursor.row as isize + rows_down;
+        let col = self.cursor.col as isize + cols_right;
+        self.cursor = Loc {
+            row: row.clamp(1, rows as isize - 2) as usize,
+            col: col.clamp(1, cols as isize - 2) as usize,
+        };
+    }
+
+    fn play(&mut self, kind: MoveKind) {
+        let mv = Move {
+            player: self.board.get_current_player(),
+            kind,
+        };
+        if !self.board.move_is_valid(&mv) {
+            if let MoveKind::Play(loc) = kind {
+                self.messages
+                    .push(format!("{}, {} is illegal, try again", loc.row, loc.col));
+            }
+            return;
+        }
+        self.board.play(&mv);
+        self.messages.push(Variant::announce(&mv));
+
+        if !self.clock.stop() {
+            self.phase = Phase::Over(GameResult::Time(mv.player.opponent()));
+        } else if self.board.resigned().is_some() {
+            self.phase = Phase::Over(self.board.final_score());
+        } else if self.board.is_over() {
+            self.phase = Phase::Counting;
+            self.messages
+                .push(String::from("Take off dead groups with Enter, 'c' counts"));
+        } else {
+            self.clock.start(self.board.get_current_player());
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => return self.move_cursor(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => return self.move_cursor(1, 0),
+            KeyCode::Left | KeyCode::Char('h') => return self.move_cursor(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => return self.move_cursor(0, 1),
+            KeyCode::Char('q') => {
+                self.phase = Phase::Over(GameResult::Void);
+                return;
+            }
+            _ => (),
+        }
+
+        match (&self.phase, code) {
+            (Phase::Playing, KeyCode::Enter | KeyCode::Char(' ')) => {
+                self.play(MoveKind::Play(self.cursor))
+            }
+            (Phase::Playing, KeyCode::Char('p')) => self.play(MoveKind::Pass),
+            (Phase::Playing, KeyCode::Char('r')) => self.play(MoveKind::Resign),
+            (Phase::Playing, KeyCode::Char('u')) => {
+                let waiting = self.board.get_current_player();
+                self.board = self.board.clone().undo();
+                self.messages
+                    .push(String::from("The last move was taken back"));
+                // The time spent before asking for the undo still counts
+                if !self.clock.start(self.board.get_current_player()) {
+                    self.phase = Phase::Over(GameResult::Time(waiting.opponent()));
+                }
+            }
+            (Phase::Counting, KeyCode::Enter | KeyCode::Char(' ')) => {
+                if matches!(self.board.get(self.cursor), Color::Black | Color::White) {
+                    self.board.remove_group(self.cursor);
+                }
+            }
+            (Phase::Counting, KeyCode::Char('c')) => {
+                self.phase = Phase::Over(self.board.final_score())
+            }
+            _ => (),
+        }
+    }
+
+    // What the variant lets the players see of the point
+    fn shown_color(&self, loc: Loc) -> Color {
+        match (self.variant, self.board.get(loc)) {
+            (Variant::Blind, _) => Color::Empty,
+            (Variant::OneColor, Color::White) => Color::Black,
+            (_, color) => color,
+        }
+    }
+
+    fn render_row(&self, row: usize) -> String {
+        let (_, cols) = self.board.board_size();
+        let last_move = self
+            .board
+            .get_game_history()
+            .last()
+            .and_then(|mv| mv.get_loc());
+        let mut line = format!("{:>2} ", row);
+        for col in 1..cols - 1 {
+            let loc = Loc { row, col };
+            let glyph = match self.shown_color(loc) {
+                Color::Empty => "+ ".dark_grey(),
+                Color::Black if last_move == Some(loc) => "◉ ".black(),
+                Color::White if last_move == Some(loc) => "◉ ".white(),
+                Color::Black => "● ".black(),
+                _ => "● ".white(),
+            };
+            let background = if loc == self.cursor {
+                CURSOR_BACKGROUND
+            } else {
+                BOARD_BACKGROUND
+            };
+            line += &glyph.on(background).to_string();
+        }
+        line
+    }
+
+    // Plain text, shown to the right of the board
+    pub fn panel(&self) -> Vec<String> {
+        let mut panel = vec![];
+        panel.push(match &self.phase {
+            Phase::Playing => format!("Turn: {:?}", self.board.get_current_player()),
+            Phase::Counting => String::from("Counting"),
+            Phase::Over(result) => format!("{} ({})", result, result.to_sgf()),
+        });
+        panel.push(format!(
+            "Captures: Black {}, White {}",
+            self.board.get_captures(Player::Black),
+            self.board.get_captures(Player::White)
+        ));
+        if self.clock.get_control() != TimeControl::Unlimited {
+            panel.push(format!("Clock: {}", self.clock));
+        }
+
+        panel.push(String::new());
+        panel.push(String::from("Moves:"));
+        let history = self.board.get_game_history();
+        let first = history.len().saturating_sub(PANEL_MOVES);
+        for (number, mv) in history.iter().enumerate().skip(first) {
+            panel.push(format!("{:>3}. {}", number + 1, describe_move(mv)));
+        }
+
+        panel.push(String::new());
+        panel.push(String::from("Messages:"));
+        let first = self.messages.len().saturating_sub(PANEL_MESSAGES);
+        panel.extend(self.messages[first..].iter().cloned());
+        panel
+    }
+
+    // The board with the panel next to it, one string per terminal line
+    pub fn render(&self) -> Vec<String> {
+        let (rows, cols) = self.board.board_size();
+        let mut board_lines = vec![String::from("   ")];
+        for col in 1..cols - 1 {
+            board_lines[0] += &format!("{:<2}", col % 100);
+        }
+        for row in 1..rows - 1 {
+            board_lines.push(self.render_row(row));
+        }
+
+        // Board lines hold escape codes, so the padding below them is computed from the board size
+        let width = 3 + 2 * (cols - 2);
+        let panel = self.panel();
+        let mut lines = vec![];
+        for i in 0..board_lines.len().max(panel.len()) {
+            let board_line = board_lines
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| " ".repeat(width));
+            let panel_line = panel.get(i).map(|line| line.as_str()).unwrap_or("");
+            lines.push(format!("{}   {}", board_line, panel_line));
+        }
+        lines.push(String::new());
+        lines.push(match self.phase {
+            Phase::Playing => String::from(
+                "Arrows or hjkl move, Enter plays, 'p' passes, 'u' undoes, 'r' resigns, 'q' quits",
+            ),
+            Phase::Counting => {
+                String::from("Arrows or hjkl move, Enter takes off a group, 'c' counts, 'q' quits")
+            }
+            Phase::Over(_) => String::from("Press any key to leave"),
+        });
+        lines
+    }
+}
+
+fn draw(stdout: &mut io::Stdout, game: &LocalGame) -> io::Result<()> {
+    queue!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+    for line in game.render() {
+        // Raw mode doesn't turn \n into \r\n
+        write!(stdout, "{}\r\n", line)?;
+    }
+    stdout.flush()
+}
+
+fn event_loop(stdout: &mut io::Stdout, game: &mut LocalGame) -> io::Result<GameResult> {
+    loop {
+        draw(stdout, game)?;
+        let code = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key.code,
+            _ => continue,
+        };
+        if let Phase::Over(result) = game.get_phase() {
+            return Ok(result.clone());
+        }
+        game.handle_key(code);
+    }
+}
+
+// Plays a game in the alternate screen and gives back its result once the players leave
+pub fn run(board: Board, variant: Variant, clock: Clock) -> io::Result<GameResult> {
+    let mut game = LocalGame::new(board, variant, clock);
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+
+    let result = event_loop(&mut stdout, &mut game);
+
+    // The terminal is restored even when the game loop failed
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Color, GameResult, Loc, Player};
+    use crate::clock::{Clock, MockTime, TimeControl};
+    use crate::tui::{LocalGame, Phase};
+    use crate::variant::Variant;
+    use crossterm::event::KeyCode;
+    use std::time::Duration;
+
+    fn new_game(variant: Variant) -> LocalGame {
+        let clock = Clock::new(TimeControl::Unlimited, Box::new(MockTime::new()));
+        LocalGame::new(Board::new(7, 7, 0.5), variant, clock)
+    }
+
+    fn press(game: &mut LocalGame, keys: &str) {
+        for key in keys.chars() {
+            let code = match key {
+                '\n' => KeyCode::Enter,
+                '<' => KeyCode::Left,
+                '>' => KeyCode::Right,
+                '^' => KeyCode::Up,
+                'v' => KeyCode::Down,
+                key => KeyCode::Char(key),
+            };
+            game.handle_key(code);
+        }
+    }
+
+    #[test]
+    fn the_cursor_moves_on_the_board_and_places_stones() {
+        let mut game = new_game(Variant::Standard);
+        assert_eq!(game.get_cursor(), Loc { row: 3, col: 3 });
+        press(&mut game, "\n^^^^<\n");
+        assert_eq!(game.get_cursor(), Loc { row: 1, col: 2 });
+        assert_eq!(game.get_board().get(Loc { row: 3, col: 3 }), Color::Black);
+        assert_eq!(game.get_board().get(Loc { row: 1, col: 2 }), Color::White);
+
+        press(&mut game, "vv>\n");
+        assert_eq!(game.panel()[7], "Messages:");
+        assert_eq!(game.panel()[10], "3, 3 is illegal, try again");
+        press(&mut game, "u");
+        assert_eq!(game.get_board().get(Loc { row: 1, col: 2 }), Color::Empty);
+    }
+
+    #[test]
+    fn the_panel_lists_captures_and_moves() {
+        let mut game = new_game(Variant::OneColor);
+        // Black captures the white stone in the corner
+        press(&mut game, "^^<\n<\nv\np");
+        let panel = game.panel();
+        assert_eq!(panel[0], "Turn: Black");
+        assert_eq!(panel[1], "Captures: Black 1, White 0");
+        assert_eq!(panel[4], "  1. Black 1, 2");
+        assert_eq!(panel[7], "  4. White passes");
+        assert_eq!(game.render().len(), 16);
+    }
+
+    #[test]
+    fn two_passes_lead_to_counting_and_resigning_ends_the_game() {
+        let mut game = new_game(Variant::Blind);
+        press(&mut game, "\n^\npp");
+        assert_eq!(game.get_phase(), &Phase::Counting);
+        press(&mut game, "\nc");
+        assert_eq!(
+            game.get_phase(),
+            &Phase::Over(GameResult::Player(Player::Black, 24.5))
+        );
+
+        let mut game = new_game(Variant::Standard);
+        press(&mut game, "r");
+        assert_eq!(
+            game.get_phase(),
+            &Phase::Over(GameResult::Resignation(Player::White))
+        );
+    }
+
+    #[test]
+    fn an_undo_after_the_flag_fell_loses_on_time() {
+        let time = MockTime::new();
+        let control = TimeControl::Absolute {
+            main: Duration::from_secs(10),
+        };
+        let clock = Clock::new(control, Box::new(time.clone()));
+        let mut game = LocalGame::new(Board::new(7, 7, 0.5), Variant::Standard, clock);
+        press(&mut game, "\n");
+        time.advance(Duration::from_secs(11));
+        press(&mut game, "u");
+        assert_eq!(
+            game.get_phase(),
+            &Phase::Over(GameResult::Time(Player::Black))
+        );
+    }
+}
@@ -0,0 +1,84 @@
+// Full information variants played on the same Board, they differ only in what the players are shown.
+// Phantom Go hides information per player and goes through the Referee instead.
+
+use crate::board::{Board, Color, Loc, Move, MoveKind};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    Standard,
+    // All stones look the same, players have to remember whose they are
+    OneColor,
+    // Nobody sees the board, moves are only read out
+    Blind,
+}
+
+impl Variant {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Variant::Standard),
+            "one-color" => Some(Variant::OneColor),
+            "blind" => Some(Variant::Blind),
+            _ => None,
+        }
+    }
+
+    // What the players are shown of the board, None when it isn't shown at all
+    pub fn render(&self, board: &Board) -> Option<String> {
+        match self {
+            Variant::Standard => Some(board.to_string()),
+            Variant::Blind => None,
+            Variant::OneColor => {
+                let (rows, cols) = board.board_size();
+                let mut board_string = String::new();
+                for row in 0..rows {
+                    for col in 0..cols {
+                        let color = match board.get(Loc { row, col }) {
+                            Color::White => Color::Black,
+                            color => color,
+                        };
+                        board_string += &format!("{} ", color);
+                    }
+                    board_string += "\n";
+                }
+                Some(board_string)
+            }
+        }
+    }
+
+    // What the referee reads out to both players after a move
+    pub fn announce(mv: &Move) -> String {
+        match mv.kind {
+            MoveKind::Play(loc) => format!("{:?} plays {}, {}", mv.player, loc.row, loc.col),
+            MoveKind::Pass => format!("{:?} passes", mv.player),
+            MoveKind::Resign => format!("{:?} resigns", mv.player),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, Loc, Move, Player};
+    use crate::variant::Variant;
+
+    #[test]
+    fn one_color_shows_all_stones_alike_and_blind_shows_nothing() {
+        let mut board = Board::new(4, 4, 0.5);
+        board.play(&Move::new(Player::Black, Loc { row: 1, col: 1 }));
+        board.play(&Move::new(Player::White, Loc { row: 2, col: 2 }));
+
+        assert_eq!(
+            Variant::OneColor.render(&board).unwrap(),
+            "/ / / / \n/ O . / \n/ . O / \n/ / / / \n"
+        );
+        assert_eq!(Variant::Standard.render(&board).unwrap(), board.to_string());
+        assert_eq!(Variant::Blind.render(&board), None);
+    }
+
+    #[test]
+    fn moves_are_read_out() {
+        let mv = Move::new(Player::White, Loc { row: 3, col: 4 });
+        assert_eq!(Variant::announce(&mv), "White plays 3, 4");
+        assert_eq!(Variant::announce(&mv.pass()), "White passes");
+        assert_eq!(Variant::announce(&mv.resign()), "White resigns");
+    }
+}